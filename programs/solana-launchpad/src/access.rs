@@ -1,9 +1,25 @@
 use crate::{
-  account::{IdoAccount, IdoTimes},
+  account::{
+    AuctionParams, IdoAccount, IdoStatus, IdoTimes, LbpParams, LotteryParams, SaleMode,
+    SealedBidParams, TierAllocation, VestingParams, WhitelistTier, BASIS_POINTS, MAX_FEE_BPS,
+    MAX_QUOTE_MINTS, MAX_WHITELIST_TIERS, WEIGHT_SCALE,
+  },
+  ed25519::Ed25519,
   error::ErrorCode,
   merkle_proof::MerkleProof,
 };
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+  load_current_index_checked, load_instruction_at_checked,
+};
+
+pub fn validate_launchpad_config(fee_bps: u64, quote_mints: &[Pubkey]) -> ProgramResult {
+  require!(
+    fee_bps <= MAX_FEE_BPS && quote_mints.len() <= MAX_QUOTE_MINTS,
+    ErrorCode::InvalidLaunchpadConfig
+  );
+  Ok(())
+}
 
 // Asserts the IDO starts in the future.
 pub fn validate_ido_times(ido_times: IdoTimes) -> ProgramResult {
@@ -17,15 +33,158 @@ pub fn validate_ido_times(ido_times: IdoTimes) -> ProgramResult {
   require!(
     ido_times.start_ido < ido_times.end_whitelisted
       && ido_times.end_whitelisted < ido_times.end_deposits
-      && ido_times.end_deposits < ido_times.end_ido,
+      && ido_times.end_deposits <= ido_times.end_reveal
+      && ido_times.end_reveal < ido_times.end_ido,
     ErrorCode::SeqTimes
   );
 
   Ok(())
 }
 
+// Asserts the soft cap can be reached under the hard cap, if there is one.
+pub fn validate_caps(soft_cap: u64, hard_cap: u64) -> ProgramResult {
+  require!(
+    hard_cap == 0 || soft_cap <= hard_cap,
+    ErrorCode::InvalidCaps
+  );
+  Ok(())
+}
+
+pub fn validate_contribution_limits(min_contribution: u64, max_contribution: u64) -> ProgramResult {
+  require!(
+    max_contribution == 0 || min_contribution <= max_contribution,
+    ErrorCode::InvalidContributionLimits
+  );
+  Ok(())
+}
+
+// Asserts the pool has what its sale mode needs to price the public allocation.
+pub fn validate_sale_mode(
+  sale_mode: SaleMode,
+  hard_cap: u64,
+  fixed_price: u64,
+  auction: AuctionParams,
+  lbp: LbpParams,
+  sealed_bid: SealedBidParams,
+  ido_times: IdoTimes,
+) -> ProgramResult {
+  require!(
+    sale_mode != SaleMode::Overflow || hard_cap > 0,
+    ErrorCode::InvalidSaleConfig
+  );
+  require!(
+    sale_mode != SaleMode::FixedPrice || fixed_price > 0,
+    ErrorCode::InvalidSaleConfig
+  );
+  require!(
+    sale_mode != SaleMode::DutchAuction
+      || (auction.floor_price > 0
+        && auction.start_price >= auction.floor_price
+        && auction.step_duration >= 0),
+    ErrorCode::InvalidSaleConfig
+  );
+  require!(
+    sale_mode != SaleMode::Lbp
+      || (lbp.virtual_usdc > 0
+        && (1..WEIGHT_SCALE).contains(&lbp.start_weight)
+        && (1..WEIGHT_SCALE).contains(&lbp.end_weight)),
+    ErrorCode::InvalidSaleConfig
+  );
+  require!(
+    sale_mode != SaleMode::SealedBid
+      || (sealed_bid.min_price > 0
+        && sealed_bid.tick_size > 0
+        && ido_times.end_reveal > ido_times.end_deposits),
+    ErrorCode::InvalidSaleConfig
+  );
+  Ok(())
+}
+
+pub fn validate_vesting(
+  private_vesting: VestingParams,
+  public_vesting: VestingParams,
+) -> ProgramResult {
+  for vesting in [private_vesting, public_vesting] {
+    require!(
+      vesting.tge_unlock_bps <= BASIS_POINTS && vesting.cliff >= 0 && vesting.period >= 0,
+      ErrorCode::InvalidVesting
+    );
+    // Without cliff nor period nothing is locked, so all of it unlocks at TGE.
+    require!(
+      vesting.is_enabled() || vesting.tge_unlock_bps == BASIS_POINTS,
+      ErrorCode::InvalidVesting
+    );
+  }
+  Ok(())
+}
+
+// Asserts every whitelist tier can be priced and fits in the pool.
+pub fn validate_whitelist_tiers(tiers: &[WhitelistTier], lottery: LotteryParams) -> ProgramResult {
+  require!(tiers.len() <= MAX_WHITELIST_TIERS, ErrorCode::TooManyTiers);
+
+  let mut lottery_tiers = 0;
+  for tier in tiers {
+    require!(
+      tier.target_investment > 0 && tier.num_ido_tokens > 0,
+      ErrorCode::InvalidTier
+    );
+    if tier.allocation == TierAllocation::Lottery {
+      require!(
+        tier.num_ido_tokens >= lottery.ticket_size,
+        ErrorCode::InvalidLottery
+      );
+      lottery_tiers += 1;
+    }
+  }
+  // The draw has a single set of winners, so it backs exactly one tier.
+  require!(
+    lottery_tiers == lottery.is_enabled() as usize,
+    ErrorCode::InvalidLottery
+  );
+
+  Ok(())
+}
+
+// Asserts the lottery registers, then draws, inside the whitelisted phase.
+pub fn validate_lottery(lottery: LotteryParams, ido_times: IdoTimes) -> ProgramResult {
+  if lottery.is_enabled() {
+    require!(
+      ido_times.start_ido < lottery.end_registration
+        && lottery.end_registration < lottery.end_draw
+        && lottery.end_draw < ido_times.end_whitelisted,
+      ErrorCode::InvalidLottery
+    );
+  }
+  Ok(())
+}
+
+// Every sale phase is closed while the pool is paused. Editing the whitelist
+// stays open, so it can be fixed before unpausing.
+pub fn not_paused(ido_account: &IdoAccount) -> ProgramResult {
+  require!(!ido_account.paused, ErrorCode::IdoPaused);
+  Ok(())
+}
+
+// A cancelled ido stays closed for good, only the refunds are open.
+pub fn not_cancelled(ido_account: &IdoAccount) -> ProgramResult {
+  require!(!ido_account.cancelled, ErrorCode::IdoCancelled);
+  Ok(())
+}
+
+pub fn ido_cancelled(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  require!(ido_account.cancelled, ErrorCode::IdoNotCancelled);
+  Ok(())
+}
+
 pub fn unrestricted_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
   let clock = Clock::get()?;
+  require!(
+    ido_account.status == IdoStatus::Approved,
+    ErrorCode::IdoNotApproved
+  );
   require!(
     clock.unix_timestamp > ido_account.ido_times.start_ido,
     ErrorCode::StartIdoTime
@@ -38,7 +197,13 @@ pub fn unrestricted_phase(ido_account: &IdoAccount) -> ProgramResult {
 }
 
 pub fn whitelisted_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
   let clock = Clock::get()?;
+  require!(
+    ido_account.status == IdoStatus::Approved,
+    ErrorCode::IdoNotApproved
+  );
   require!(
     clock.unix_timestamp > ido_account.ido_times.start_ido,
     ErrorCode::StartIdoTime
@@ -52,9 +217,41 @@ pub fn whitelisted_phase(ido_account: &IdoAccount) -> ProgramResult {
   Ok(())
 }
 
+// Asserts stake can still be registered for the IDO, i.e. it has not started.
+pub fn stake_registration_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
+  let clock = Clock::get()?;
+  require!(
+    ido_account.status == IdoStatus::Approved,
+    ErrorCode::IdoNotApproved
+  );
+  require!(
+    clock.unix_timestamp < ido_account.ido_times.start_ido,
+    ErrorCode::StakeRegistrationClosed
+  );
+  Ok(())
+}
+
+// Asserts the whitelist can still be edited, i.e. the whitelisted phase has not ended.
+pub fn whitelist_editable(ido_account: &IdoAccount) -> ProgramResult {
+  let clock = Clock::get()?;
+  require!(
+    clock.unix_timestamp < ido_account.ido_times.end_whitelisted,
+    ErrorCode::EndWhitelistedTime
+  );
+  Ok(())
+}
+
 pub fn deposit_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
   let clock = Clock::get()?;
 
+  require!(
+    ido_account.status == IdoStatus::Approved,
+    ErrorCode::IdoNotApproved
+  );
   msg!("{}", clock.unix_timestamp);
   msg!("{}", ido_account.ido_times.end_whitelisted);
 
@@ -70,7 +267,66 @@ pub fn deposit_phase(ido_account: &IdoAccount) -> ProgramResult {
   Ok(())
 }
 
+// Asserts the IDO is a liquidity bootstrapping pool that is open for swaps.
+pub fn lbp_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
+  let clock = Clock::get()?;
+
+  require!(
+    ido_account.status == IdoStatus::Approved,
+    ErrorCode::IdoNotApproved
+  );
+  require!(
+    ido_account.sale_mode == SaleMode::Lbp,
+    ErrorCode::InvalidSaleMode
+  );
+  require!(
+    clock.unix_timestamp > ido_account.ido_times.start_ido,
+    ErrorCode::StartIdoTime
+  );
+  require!(
+    clock.unix_timestamp < ido_account.ido_times.end_ido,
+    ErrorCode::EndIdoTime
+  );
+
+  Ok(())
+}
+
+// Asserts sealed bids can be revealed, i.e. deposits are closed and the reveal period is on.
+pub fn reveal_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
+  let clock = Clock::get()?;
+
+  require!(
+    clock.unix_timestamp > ido_account.ido_times.end_deposits,
+    ErrorCode::IdoNotOver
+  );
+  require!(
+    clock.unix_timestamp < ido_account.ido_times.end_reveal,
+    ErrorCode::EndReveal
+  );
+
+  Ok(())
+}
+
+pub fn reveal_over(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
+  let clock = Clock::get()?;
+
+  require!(
+    clock.unix_timestamp > ido_account.ido_times.end_reveal,
+    ErrorCode::RevealNotOver
+  );
+
+  Ok(())
+}
+
 pub fn withdraw_phase(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
+  not_cancelled(ido_account)?;
   let clock = Clock::get()?;
 
   require!(
@@ -80,7 +336,27 @@ pub fn withdraw_phase(ido_account: &IdoAccount) -> ProgramResult {
   Ok(())
 }
 
+// Redeemable is refunded once deposits close, or right away when the ido is cancelled.
+pub fn refund_phase(ido_account: &IdoAccount) -> ProgramResult {
+  if ido_account.cancelled {
+    return ido_cancelled(ido_account);
+  }
+  withdraw_phase(ido_account)
+}
+
+// Asserts the ido can still be pulled, i.e. it has not ended.
+pub fn cancellable(ido_account: &IdoAccount) -> ProgramResult {
+  let clock = Clock::get()?;
+  require!(!ido_account.cancelled, ErrorCode::IdoCancelled);
+  require!(
+    clock.unix_timestamp < ido_account.ido_times.end_ido,
+    ErrorCode::EndIdoTime
+  );
+  Ok(())
+}
+
 pub fn ido_is_over(ido_account: &IdoAccount) -> ProgramResult {
+  not_paused(ido_account)?;
   let clock = Clock::get()?;
 
   require!(
@@ -91,8 +367,14 @@ pub fn ido_is_over(ido_account: &IdoAccount) -> ProgramResult {
   Ok(())
 }
 
-pub fn only_for_whitelisted(proof: Vec<[u8; 32]>, root: [u8; 32], value: &[u8]) -> ProgramResult {
-  let leaf = MerkleProof::calc_leaf_hash(value);
+// Asserts the (wallet, max_amount) allocation is part of the whitelist.
+pub fn only_for_whitelisted(
+  proof: Vec<[u8; 32]>,
+  root: [u8; 32],
+  user: &Pubkey,
+  max_amount: u64,
+) -> ProgramResult {
+  let leaf = MerkleProof::calc_leaf_hash(&[user.as_ref(), &max_amount.to_le_bytes()].concat());
 
   require!(
     MerkleProof::verify(proof, root, leaf),
@@ -101,3 +383,37 @@ pub fn only_for_whitelisted(proof: Vec<[u8; 32]>, root: [u8; 32], value: &[u8])
 
   Ok(())
 }
+
+// Asserts the instruction right before this one is an Ed25519 signature check of
+// `ido || user || tier || max_amount || expiry` by the allowlist signer.
+pub fn only_for_signed(
+  instructions: &AccountInfo,
+  signer: &Pubkey,
+  ido_account: &Pubkey,
+  user: &Pubkey,
+  tier: u8,
+  max_amount: u64,
+  expiry: i64,
+) -> ProgramResult {
+  let clock = Clock::get()?;
+  require!(clock.unix_timestamp <= expiry, ErrorCode::AllowlistExpired);
+
+  let current = load_current_index_checked(instructions)?;
+  require!(current > 0, ErrorCode::InvalidSignature);
+  let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+
+  let message = [
+    ido_account.as_ref(),
+    user.as_ref(),
+    &[tier],
+    &max_amount.to_le_bytes(),
+    &expiry.to_le_bytes(),
+  ]
+  .concat();
+  require!(
+    Ed25519::verify(&ix, signer, &message),
+    ErrorCode::InvalidSignature
+  );
+
+  Ok(())
+}
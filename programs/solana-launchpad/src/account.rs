@@ -1,24 +1,749 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program_pack::IsInitialized;
 
+use crate::{
+  error::ErrorCode,
+  fixed_point::{FixedPoint, ONE},
+};
+
+pub const MAX_WHITELIST_TIERS: usize = 4;
+// Auction prices are USDC paid per PRICE_SCALE watermelon.
+pub const PRICE_SCALE: u64 = 1_000_000;
+// LBP weights are out of WEIGHT_SCALE, the USDC side gets the remainder.
+pub const WEIGHT_SCALE: u64 = 10_000;
+pub const MAX_PRICE_LEVELS: usize = 32;
+pub const BASIS_POINTS: u64 = 10_000;
+// Highest protocol fee the admin can set, 10% of the proceeds.
+pub const MAX_FEE_BPS: u64 = 1_000;
+pub const MAX_QUOTE_MINTS: usize = 4;
+
+#[account]
+#[derive(Default)]
+pub struct LaunchpadConfig {
+  pub admin: Pubkey,
+  pub treasury: Pubkey, // Owner of the USDC accounts protocol fees are paid to.
+  pub fee_bps: u64,     // Cut of every ido's proceeds taken by the protocol.
+  pub num_quote_mints: u8,
+  pub quote_mints: [Pubkey; MAX_QUOTE_MINTS], // Mints pools can raise in, any mint when empty.
+  pub paused: bool,                           // Stops new pools from being created.
+  pub bump: u8,
+  pub num_idos: u64, // Idos in the registry, the next one is registered at this index.
+}
+
+impl LaunchpadConfig {
+  pub fn allows_quote_mint(&self, mint: &Pubkey) -> bool {
+    let quote_mints = &self.quote_mints[..self.num_quote_mints as usize];
+    quote_mints.is_empty() || quote_mints.contains(mint)
+  }
+
+  pub fn set_quote_mints(&mut self, quote_mints: &[Pubkey]) {
+    self.quote_mints = Default::default();
+    self.quote_mints[..quote_mints.len()].copy_from_slice(quote_mints);
+    self.num_quote_mints = quote_mints.len() as u8;
+  }
+}
+
 #[account]
 #[derive(Default)]
 pub struct IdoAccount {
   pub ido_name: [u8; 10], // Setting an arbitrary max of ten characters in the ido name.
-  pub merkle_root: [u8; 32],
   pub bumps: PoolBumps,
+  pub registry_index: u64, // Index of the registry entry, tells apart pools registered under the same name.
   pub ido_authority: Pubkey,
+  pub status: IdoStatus, // Set by the launchpad admin, only approved idos open.
+  pub paused: bool,      // Emergency stop, every phase guard rejects while set.
+  pub cancelled: bool,   // Pulled before end_ido, buyers get their USDC back instead.
+  pub fee_bps: u64,      // Protocol fee at pool creation, later config changes don't apply.
   pub usdc_mint: Pubkey,
   pub redeemable_mint: Pubkey,
   pub watermelon_mint: Pubkey,
   pub pool_usdc: Pubkey,
   pub pool_native: Pubkey,
   pub pool_watermelon: Pubkey,
-  pub private_target_investment: u64,
-  pub num_ido_tokens_private: u64,
+  pub num_ido_tokens_private: u64, // Sum of the allocations of every whitelist tier.
+  pub private_sold: u64,
+  pub private_raised: u64, // USDC paid through the whitelist tiers, net of cancellation refunds.
   pub num_ido_tokens_public: u64,
+  pub soft_cap: u64, // Minimum USDC the public sale must raise, otherwise depositors are refunded.
+  pub hard_cap: u64, // Maximum redeemable that can be minted, zero for no cap.
+  pub min_contribution: u64, // Smallest USDC deposit accepted in the public sale.
+  pub max_contribution: u64, // Most USDC a single wallet can deposit, zero for no limit.
+  pub sale_mode: SaleMode,
+  pub fixed_price: u64, // USDC paid per PRICE_SCALE watermelon in a fixed-price sale.
+  pub auction: AuctionParams,
+  pub auction_sold: u64,    // Watermelon sold by a dutch or sealed-bid auction.
+  pub auction_claimed: u64, // Watermelon already paid out to auction bidders.
+  pub clearing_price: u64, // Price the dutch auction sold out at, or the sealed-bid auction cleared at.
+  pub lbp: LbpParams,
+  pub lbp_usdc_in: u64,    // USDC swapped into the liquidity bootstrapping pool.
+  pub lbp_tokens_out: u64, // Watermelon swapped out of the liquidity bootstrapping pool.
+  pub sealed_bid: SealedBidParams,
+  pub bid_demand: [u64; MAX_PRICE_LEVELS], // Watermelon asked for by revealed bids, per price level.
+  pub bid_escrow: u64,                     // USDC escrowed by bids that are not settled yet.
+  pub bids_open: u64,                      // Committed bids that are not settled yet.
+  pub auction_settled: bool,               // Set once the sealed-bid clearing price is computed.
+  pub private_vesting: VestingParams,      // Schedule for watermelon bought through the whitelist.
+  pub public_vesting: VestingParams,       // Schedule for watermelon claimed with redeemable.
+  pub vesting_locked: u64, // Watermelon locked in vesting accounts, not released yet.
   pub ido_times: IdoTimes,
   pub pool_info: PoolInfo,
+  pub num_tiers: u8,
+  pub tiers: [WhitelistTier; MAX_WHITELIST_TIERS],
+  pub total_stake_snapshot: u64, // Stake registered for this ido, splits the stake-weighted tiers.
+  pub lottery: LotteryParams,
+  pub lottery_tickets: u64, // Tickets registered, numbered in registration order.
+  pub lottery_entropy: [u8; 32], // Running hash of the registered wallets, mixed into the draw.
+  pub lottery_slot: u64,    // Slot the lottery closed in, its hash is mixed into the draw.
+  pub lottery_offset: u64,  // First winning ticket, set by the draw.
+  pub lottery_drawn: bool,
+  pub allowlist_signer: Pubkey, // Backend key that signs allocations for the signature tiers.
+  pub unsold_withdrawn: u64,    // Unsold watermelon already returned to the ido authority.
+}
+
+impl IdoAccount {
+  /// Protocol cut of the proceeds, at the fee the pool was created with.
+  pub fn protocol_fee(&self, amount: u64) -> u64 {
+    let fee = (amount as u128)
+      .checked_mul(self.fee_bps as u128)
+      .unwrap()
+      .checked_div(BASIS_POINTS as u128)
+      .unwrap();
+    fee as u64
+  }
+
+  /// Private allocation that was not bought through the whitelist tiers.
+  pub fn unsold_private(&self) -> Result<u64, ProgramError> {
+    let unsold = self
+      .num_ido_tokens_private
+      .checked_sub(self.private_sold)
+      .ok_or(ErrorCode::MathOverflow)?;
+    Ok(unsold)
+  }
+
+  /// Public allocation left over once every redeemable is claimed.
+  pub fn unsold_public(&self) -> Result<u64, ProgramError> {
+    let raised = self.pool_info.redeemable_minted;
+    let sold = match self.sale_mode {
+      // Every redeemable shares the whole allocation, unless nobody deposited.
+      SaleMode::Floating if raised == 0 => 0,
+      SaleMode::Floating => return Ok(0),
+      SaleMode::FixedPrice => self.fixed_price_amount(raised),
+      SaleMode::DutchAuction => self.auction_sold,
+      SaleMode::Lbp => self.lbp_tokens_out,
+      SaleMode::SealedBid if !self.auction_settled => return Ok(0),
+      // Pro-rata fills round down, the dust is only known once every bid is settled.
+      SaleMode::SealedBid if self.bids_open > 0 => self.auction_sold,
+      SaleMode::SealedBid => self.auction_claimed,
+      SaleMode::Overflow if raised >= self.hard_cap => return Ok(0),
+      SaleMode::Overflow => {
+        let sold = (raised as u128)
+          .checked_mul(self.num_ido_tokens_public as u128)
+          .unwrap()
+          .checked_div(self.hard_cap as u128)
+          .unwrap();
+        sold as u64
+      }
+    };
+    let unsold = self
+      .num_ido_tokens_public
+      .checked_sub(sold)
+      .ok_or(ErrorCode::MathOverflow)?;
+    Ok(unsold)
+  }
+
+  /// Watermelon the ido authority can take back: the unsold private and public
+  /// allocations, or the whole public allocation when the sale missed its soft cap.
+  pub fn unsold(&self) -> Result<u64, ProgramError> {
+    let unsold_public = if self.pool_info.is_initialized() && !self.soft_cap_reached() {
+      self.num_ido_tokens_public
+    } else if self.pool_info.is_initialized() {
+      self.unsold_public()?
+    } else {
+      0
+    };
+    let unsold = self
+      .unsold_private()?
+      .checked_add(unsold_public)
+      .ok_or(ErrorCode::MathOverflow)?;
+    Ok(unsold)
+  }
+
+  /// Watermelon in `pool_watermelon` that still belongs to buyers of a cancelled ido:
+  /// locked vesting and bids of an auction settled before the cancellation.
+  pub fn owed_watermelon(&self) -> Result<u64, ProgramError> {
+    let unsettled = if self.auction_settled {
+      self
+        .auction_sold
+        .checked_sub(self.auction_claimed)
+        .ok_or(ErrorCode::MathOverflow)?
+    } else {
+      0
+    };
+    let owed = self
+      .vesting_locked
+      .checked_add(unsettled)
+      .ok_or(ErrorCode::MathOverflow)?;
+    Ok(owed)
+  }
+
+  /// Records the redeemable supply once deposits are closed. Every public payout
+  /// is computed from this snapshot, as claims burn redeemable afterwards.
+  pub fn snapshot_redeemable(&mut self, redeemable_supply: u64) {
+    if !self.pool_info.is_initialized() {
+      self.pool_info.is_initialized = true;
+      self.pool_info.redeemable_minted = redeemable_supply;
+    }
+  }
+
+  // An overflow sale takes deposits past the hard cap and scales them down at claim.
+  pub fn hard_cap_allows(&self, redeemable_supply: u64) -> bool {
+    self.hard_cap == 0 || self.sale_mode == SaleMode::Overflow || redeemable_supply <= self.hard_cap
+  }
+
+  // A fixed-price sale can't take more deposits than the public allocation pays out.
+  pub fn public_allocation_allows(&self, redeemable_supply: u64) -> bool {
+    self.sale_mode != SaleMode::FixedPrice
+      || self.fixed_price_amount(redeemable_supply) <= self.num_ido_tokens_public
+  }
+
+  /// Auction price at `now`, decaying from the start price at `end_whitelisted`, when
+  /// the public sale opens, to the floor price at `end_deposits`, either linearly or
+  /// once every `step_duration`.
+  pub fn auction_price(&self, now: i64) -> u64 {
+    let auction = &self.auction;
+    let start = self.ido_times.end_whitelisted;
+    let duration = self.ido_times.end_deposits - start;
+    let mut elapsed = (now - start).clamp(0, duration);
+    if auction.step_duration > 0 {
+      elapsed -= elapsed % auction.step_duration;
+    }
+    let decay = ((auction.start_price - auction.floor_price) as u128)
+      .checked_mul(elapsed as u128)
+      .unwrap()
+      .checked_div(duration as u128)
+      .unwrap();
+    auction.start_price - decay as u64
+  }
+
+  /// Everyone pays the price the auction sold out at, or the floor price if it didn't.
+  pub fn auction_clearing_price(&self) -> u64 {
+    if self.clearing_price > 0 {
+      self.clearing_price
+    } else {
+      self.auction.floor_price
+    }
+  }
+
+  /// USDC taken and watermelon bid for when depositing `amount_in` at `price`.
+  /// A bid larger than what's left of the public allocation only buys the remainder.
+  pub fn auction_bid(&self, amount_in: u64, price: u64) -> (u64, u64) {
+    let remaining = (self.num_ido_tokens_public - self.auction_sold) as u128;
+    let cost = remaining
+      .checked_mul(price as u128)
+      .unwrap()
+      .checked_add(PRICE_SCALE as u128 - 1)
+      .unwrap()
+      .checked_div(PRICE_SCALE as u128)
+      .unwrap();
+    let amount_in = (amount_in as u128).min(cost);
+    let tokens = amount_in
+      .checked_mul(PRICE_SCALE as u128)
+      .unwrap()
+      .checked_div(price as u128)
+      .unwrap()
+      .min(remaining);
+    (amount_in as u64, tokens as u64)
+  }
+
+  /// Watermelon weight of the LBP at `now`, shifting linearly from the start weight
+  /// at `start_ido` to the end weight at `end_ido`.
+  pub fn lbp_weight(&self, now: i64) -> u64 {
+    let lbp = &self.lbp;
+    let start = self.ido_times.start_ido;
+    let duration = self.ido_times.end_ido - start;
+    let elapsed = (now - start).clamp(0, duration) as i128;
+    let shift = (lbp.end_weight as i128 - lbp.start_weight as i128) * elapsed / duration as i128;
+    (lbp.start_weight as i128 + shift) as u64
+  }
+
+  /// USDC balance the LBP prices against. It is tracked apart from `pool_usdc`, which
+  /// also holds the whitelist proceeds, and starts from `virtual_usdc` so the first
+  /// swap has a price.
+  pub fn lbp_balance_in(&self) -> u64 {
+    self.lbp.virtual_usdc.checked_add(self.lbp_usdc_in).unwrap()
+  }
+
+  /// Watermelon bought by swapping `amount_in` USDC at `now`, following the weighted
+  /// constant product `out = balance_out * (1 - (balance_in / (balance_in + in))^(w_in / w_out))`.
+  /// Rounding the base up and the exponent down both shrink the output, so the pool
+  /// never pays out more than the exact curve. `swap` caps `amount_in` at half the
+  /// USDC balance, which keeps the base above 2/3.
+  pub fn lbp_amount_out(&self, amount_in: u64, now: i64) -> u64 {
+    let weight_out = self.lbp_weight(now) as u128;
+    let weight_in = WEIGHT_SCALE as u128 - weight_out;
+    let balance_in = self.lbp_balance_in() as u128;
+    let balance_out = (self.num_ido_tokens_public - self.lbp_tokens_out) as u128;
+
+    let balance_after = balance_in.checked_add(amount_in as u128).unwrap();
+    let base = (balance_in * ONE)
+      .checked_add(balance_after - 1)
+      .unwrap()
+      .checked_div(balance_after)
+      .unwrap();
+    let exp = weight_in * ONE / weight_out;
+    let pow = FixedPoint::pow_up(base, exp);
+    let amount_out = balance_out.checked_mul(ONE - pow).unwrap() / ONE;
+    amount_out as u64
+  }
+
+  /// Index of `price` on the sealed-bid price grid, if it is on it.
+  pub fn price_level(&self, price: u64) -> Option<usize> {
+    let params = &self.sealed_bid;
+    if price < params.min_price
+      || (price - params.min_price).checked_rem(params.tick_size) != Some(0)
+    {
+      return None;
+    }
+    let level = ((price - params.min_price) / params.tick_size) as usize;
+    if level < MAX_PRICE_LEVELS {
+      Some(level)
+    } else {
+      None
+    }
+  }
+
+  /// Walks the price levels down from the top until the revealed demand covers the
+  /// public allocation. Returns the clearing price and the watermelon sold, which is
+  /// everything bid for when demand falls short.
+  pub fn sealed_bid_clearing(&self) -> (u64, u64) {
+    let mut clearing_price = 0;
+    let mut demand: u64 = 0;
+    for level in (0..MAX_PRICE_LEVELS).rev() {
+      if self.bid_demand[level] == 0 {
+        continue;
+      }
+      clearing_price = self.sealed_bid.min_price + level as u64 * self.sealed_bid.tick_size;
+      demand = demand.checked_add(self.bid_demand[level]).unwrap();
+      if demand >= self.num_ido_tokens_public {
+        return (clearing_price, self.num_ido_tokens_public);
+      }
+    }
+    (clearing_price, demand)
+  }
+
+  /// Watermelon won by `bid` in the settled auction. Bids above the clearing price
+  /// are filled in full, bids at it share what is left pro-rata.
+  pub fn bid_fill(&self, bid: &Bid) -> Result<u64, ProgramError> {
+    if !bid.revealed || bid.price < self.clearing_price {
+      return Ok(0);
+    }
+    if bid.price > self.clearing_price {
+      return Ok(bid.amount);
+    }
+    let level = self
+      .price_level(bid.price)
+      .ok_or(ErrorCode::InvalidBidPrice)?;
+    let demand_above = self.bid_demand[level + 1..]
+      .iter()
+      .try_fold(0u64, |sum, demand| sum.checked_add(*demand))
+      .ok_or(ErrorCode::MathOverflow)?;
+    let left = self
+      .auction_sold
+      .checked_sub(demand_above)
+      .ok_or(ErrorCode::MathOverflow)?;
+    let fill = (bid.amount as u128)
+      .checked_mul(left as u128)
+      .and_then(|fill| fill.checked_div(self.bid_demand[level] as u128))
+      .ok_or(ErrorCode::MathOverflow)?;
+    Ok(fill as u64)
+  }
+
+  /// USDC a sealed bid is charged for `tokens` watermelon at `price`, rounded up.
+  pub fn bid_cost(tokens: u64, price: u64) -> u64 {
+    let cost = (tokens as u128)
+      .checked_mul(price as u128)
+      .unwrap()
+      .checked_add(PRICE_SCALE as u128 - 1)
+      .unwrap()
+      .checked_div(PRICE_SCALE as u128)
+      .unwrap();
+    cost as u64
+  }
+
+  // Redeemable is minted one-to-one for USDC, so the snapshot is the public raise.
+  // `pool_usdc` can't be used as it also holds the whitelist proceeds.
+  // LBPs and sealed-bid auctions take no redeemable deposits, so there is nothing to refund.
+  pub fn soft_cap_reached(&self) -> bool {
+    matches!(self.sale_mode, SaleMode::Lbp | SaleMode::SealedBid)
+      || self.pool_info.redeemable_minted >= self.soft_cap
+  }
+
+  /// Watermelon paid out and USDC refunded for `amount_in` redeemable.
+  pub fn claim_amounts(&self, amount_in: u64, deposit_ledger: &DepositLedger) -> (u64, u64) {
+    let raised = self.pool_info.redeemable_minted;
+    match self.sale_mode {
+      SaleMode::Floating => {
+        let amount_out = (amount_in as u128)
+          .checked_mul(self.num_ido_tokens_public as u128)
+          .unwrap()
+          .checked_div(raised as u128)
+          .unwrap();
+        (amount_out as u64, 0)
+      }
+      SaleMode::FixedPrice => (self.fixed_price_amount(amount_in), 0),
+      SaleMode::DutchAuction => {
+        // Bids are made at the price of the moment, so each wallet gets what it bid
+        // for and whatever it paid above the clearing price back.
+        let amount_out = (amount_in as u128)
+          .checked_mul(deposit_ledger.tokens_bid as u128)
+          .unwrap()
+          .checked_div(deposit_ledger.usdc_deposited as u128)
+          .unwrap_or(0) as u64;
+        let cost = self.auction_cost(amount_out, true);
+        (amount_out, amount_in.saturating_sub(cost))
+      }
+      // LBP and sealed-bid buyers get their watermelon through `swap` and `settle_bid`.
+      SaleMode::Lbp | SaleMode::SealedBid => (0, 0),
+      SaleMode::Overflow => {
+        // Tokens are priced at hard_cap / num_ido_tokens_public. When oversubscribed,
+        // everyone gets hard_cap / raised of their deposit filled and the rest back.
+        let amount_out = (amount_in as u128)
+          .checked_mul(self.num_ido_tokens_public as u128)
+          .unwrap()
+          .checked_div(raised.max(self.hard_cap) as u128)
+          .unwrap();
+        (amount_out as u64, self.overflow_refund(amount_in))
+      }
+    }
+  }
+
+  /// USDC in `pool_usdc` that is still owed to holders of `redeemable_supply`.
+  pub fn reserved_usdc(&self, redeemable_supply: u64) -> Result<u64, ProgramError> {
+    if self.cancelled {
+      // Every depositor, unsettled bidder and whitelisted buyer can still ask for a refund.
+      let reserved = redeemable_supply
+        .checked_add(self.bid_escrow)
+        .and_then(|reserved| reserved.checked_add(self.private_raised))
+        .ok_or(ErrorCode::MathOverflow)?;
+      return Ok(reserved);
+    }
+    if !self.soft_cap_reached() {
+      return Ok(redeemable_supply);
+    }
+    let reserved = match self.sale_mode {
+      SaleMode::Floating | SaleMode::FixedPrice | SaleMode::Lbp => 0,
+      SaleMode::Overflow => self.overflow_refund(redeemable_supply),
+      SaleMode::DutchAuction => {
+        let unclaimed = self
+          .auction_sold
+          .checked_sub(self.auction_claimed)
+          .ok_or(ErrorCode::MathOverflow)?;
+        redeemable_supply
+          .checked_sub(self.auction_cost(unclaimed, false))
+          .ok_or(ErrorCode::MathOverflow)?
+      }
+      // Settled bids already paid for their fill, unsettled ones may still be refunded.
+      SaleMode::SealedBid => self.bid_escrow,
+    };
+    Ok(reserved)
+  }
+
+  // A stake-weighted tier is split in proportion to the stake each wallet
+  // registered before the ido started.
+  pub fn stake_allocation(&self, tier: u8, stake: u64) -> u64 {
+    if self.total_stake_snapshot == 0 {
+      return 0;
+    }
+    let allocation = (self.tiers[tier as usize].num_ido_tokens as u128)
+      .checked_mul(stake as u128)
+      .unwrap()
+      .checked_div(self.total_stake_snapshot as u128)
+      .unwrap();
+    allocation as u64
+  }
+
+  // Winners are a window of consecutive ticket numbers, wrapping around, that
+  // starts at the drawn offset. Every ticket has the same odds. When the draw
+  // misses its deadline every ticket wins, the tier sells first come first served.
+  pub fn lottery_won(&self, tier: u8, ticket: u64, now: i64) -> bool {
+    if !self.lottery_drawn {
+      return now >= self.lottery.end_draw;
+    }
+    if self.lottery_tickets == 0 {
+      return false;
+    }
+    let winners = self.tiers[tier as usize].num_ido_tokens / self.lottery.ticket_size;
+    let rank = (ticket + self.lottery_tickets - self.lottery_offset) % self.lottery_tickets;
+    rank < winners
+  }
+
+  // Claims round the cost up, so the refunds never add up to more than is reserved.
+  pub fn auction_cost(&self, tokens: u64, round_up: bool) -> u64 {
+    let mut cost = (tokens as u128)
+      .checked_mul(self.auction_clearing_price() as u128)
+      .unwrap();
+    if round_up {
+      cost = cost.checked_add(PRICE_SCALE as u128 - 1).unwrap();
+    }
+    cost.checked_div(PRICE_SCALE as u128).unwrap() as u64
+  }
+
+  // Rounds down in the pool's favour. Deposits are capped by
+  // `public_allocation_allows`, so this fits the public allocation.
+  fn fixed_price_amount(&self, amount_in: u64) -> u64 {
+    let amount_out = (amount_in as u128)
+      .checked_mul(PRICE_SCALE as u128)
+      .unwrap()
+      .checked_div(self.fixed_price as u128)
+      .unwrap();
+    amount_out.min(u64::MAX as u128) as u64
+  }
+
+  // Rounds down, so the refunds of every claim never add up to more than the
+  // refund of the whole supply.
+  fn overflow_refund(&self, amount_in: u64) -> u64 {
+    let raised = self.pool_info.redeemable_minted;
+    if raised <= self.hard_cap {
+      return 0;
+    }
+    let refund = (amount_in as u128)
+      .checked_mul((raised - self.hard_cap) as u128)
+      .unwrap()
+      .checked_div(raised as u128)
+      .unwrap();
+    refund as u64
+  }
+}
+
+// Registry entries are numbered in creation order, so every ido can be listed by
+// walking the indices up to `LaunchpadConfig::num_idos`.
+#[account]
+#[derive(Default)]
+pub struct IdoRegistryEntry {
+  pub index: u64,
+  pub ido_account: Pubkey,
+  pub ido_name: [u8; 10],
+  pub ido_authority: Pubkey,
+  pub usdc_mint: Pubkey,
+  pub watermelon_mint: Pubkey,
+  pub ido_times: IdoTimes,
+  pub closed: bool, // Set once the ido is rejected and closed, its name can be registered again.
+}
+
+#[account]
+#[derive(Default)]
+pub struct WhitelistLedger {
+  pub user_authority: Pubkey,
+  pub ido_account: Pubkey,
+  pub tier: u8,        // Each tier keeps its own ledger, allocations don't carry over.
+  pub max_amount: u64, // Allocation committed in the whitelist leaf, as of the last purchase.
+  pub usdc_paid: u64,
+  pub watermelon_received: u64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct DepositLedger {
+  pub user_authority: Pubkey,
+  pub ido_account: Pubkey,
+  pub usdc_deposited: u64, // Net of withdrawals, counted against the max contribution.
+  pub tokens_bid: u64,     // Watermelon bid for in a dutch auction, net of claims.
+}
+
+#[account]
+#[derive(Default)]
+pub struct Vesting {
+  pub user_authority: Pubkey,
+  pub ido_account: Pubkey,
+  pub private_total: u64, // Watermelon bought through the whitelist, locked until it vests.
+  pub private_released: u64,
+  pub public_total: u64, // Watermelon claimed with redeemable, locked until it vests.
+  pub public_released: u64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Bid {
+  pub user_authority: Pubkey,
+  pub ido_account: Pubkey,
+  pub commitment: [u8; 32], // keccak(price || amount || salt), integers little-endian.
+  pub escrow: u64,
+  pub price: u64,  // USDC per PRICE_SCALE watermelon, set on reveal.
+  pub amount: u64, // Watermelon asked for, set on reveal.
+  pub revealed: bool,
+}
+
+#[account]
+#[derive(Default)]
+pub struct StakePool {
+  pub stake_mint: Pubkey, // Platform token users lock to earn allocation.
+  pub stake_vault: Pubkey,
+  pub lock_period: i64, // Seconds a stake stays locked after it was last topped up.
+  pub total_staked: u64,
+  pub bumps: StakePoolBumps,
+}
+
+#[account]
+#[derive(Default)]
+pub struct StakeAccount {
+  pub user_authority: Pubkey,
+  pub amount: u64,
+  pub locked_until: i64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct StakeSnapshot {
+  pub user_authority: Pubkey,
+  pub ido_account: Pubkey,
+  pub amount: u64, // Stake registered for the ido, locked until the private round ends.
+}
+
+#[account]
+#[derive(Default)]
+pub struct LotteryTicket {
+  pub user_authority: Pubkey,
+  pub ido_account: Pubkey,
+  pub number: u64,
+}
+
+/// Review state of an ido, pools are created pending and the launchpad admin
+/// approves or rejects them.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy, PartialEq)]
+pub enum IdoStatus {
+  #[default]
+  Pending,
+  Approved,
+  Rejected,
+}
+
+/// How a whitelist tier decides who can buy and how much.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy, PartialEq)]
+pub enum TierAllocation {
+  /// Wallets and caps are committed to by the tier's merkle root.
+  #[default]
+  Merkle,
+  /// Split in proportion to the stake each wallet snapshot before the ido started.
+  StakeWeighted,
+  /// Registered tickets are drawn, each winner can buy `LotteryParams::ticket_size`.
+  Lottery,
+  /// Wallets and caps are signed off-chain by the ido's `allowlist_signer`.
+  Signature,
+}
+
+/// How the public allocation is priced against the redeemable deposits.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy, PartialEq)]
+pub enum SaleMode {
+  /// Price floats: `num_ido_tokens_public` is split pro-rata between all deposits.
+  #[default]
+  Floating,
+  /// Fixed price of `hard_cap / num_ido_tokens_public`, oversubscription is refunded.
+  Overflow,
+  /// Fixed price of `fixed_price`, deposits close once the allocation is sold out.
+  FixedPrice,
+  /// Descending price set by `AuctionParams`, everyone clears at the final price.
+  DutchAuction,
+  /// Liquidity bootstrapping pool, users `swap` USDC for watermelon at `LbpParams` weights.
+  Lbp,
+  /// Sealed bids are committed, revealed and settled at a uniform clearing price.
+  SealedBid,
+}
+
+/// Sale configuration passed to `initialize_pool`, copied field by field into the IdoAccount.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct PoolParams {
+  pub soft_cap: u64,
+  pub hard_cap: u64,
+  pub min_contribution: u64,
+  pub max_contribution: u64,
+  pub sale_mode: SaleMode,
+  pub fixed_price: u64,
+  pub auction: AuctionParams,
+  pub lbp: LbpParams,
+  pub sealed_bid: SealedBidParams,
+  pub private_vesting: VestingParams,
+  pub public_vesting: VestingParams,
+  pub lottery: LotteryParams,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct AuctionParams {
+  pub start_price: u64,
+  pub floor_price: u64,
+  pub step_duration: i64, // Seconds between price drops, zero for a linear decay.
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct LbpParams {
+  pub start_weight: u64, // Watermelon weight out of WEIGHT_SCALE.
+  pub end_weight: u64,
+  pub virtual_usdc: u64, // USDC balance the pool prices against before any swap.
+}
+
+/// The ido authority commits to `seed_hash = keccak(seed)` at init. Once
+/// registration ends anyone closes the lottery, and the authority reveals the
+/// seed before `end_draw`. The draw mixes in the hash of the closing slot,
+/// which nobody knows before registration is over.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct LotteryParams {
+  pub seed_hash: [u8; 32],
+  pub ticket_size: u64, // Watermelon each winning ticket can buy, zero when there is no lottery.
+  pub end_registration: i64, // No tickets are registered from then on.
+  pub end_draw: i64,    // Every ticket can buy if the lottery is not drawn by then.
+}
+
+impl LotteryParams {
+  pub fn is_enabled(&self) -> bool {
+    self.ticket_size > 0
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct SealedBidParams {
+  pub min_price: u64, // Lowest bid price, in USDC per PRICE_SCALE watermelon.
+  pub tick_size: u64, // Bid prices are the min price plus a multiple of the tick size.
+}
+
+/// Vesting starts when deposits close: the TGE unlock is released right away, the rest
+/// linearly over `period` once the `cliff` has passed. Durations are in seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct VestingParams {
+  pub tge_unlock_bps: u64,
+  pub cliff: i64,
+  pub period: i64,
+}
+
+impl VestingParams {
+  // A schedule without cliff nor period unlocks everything at once, so claims
+  // keep paying out directly.
+  pub fn is_enabled(&self) -> bool {
+    self.cliff > 0 || self.period > 0
+  }
+
+  /// Part of `total` unlocked at `now` for a schedule starting at `start`.
+  pub fn unlocked(&self, total: u64, start: i64, now: i64) -> u64 {
+    if now < start {
+      return 0;
+    }
+    let tge = (total as u128)
+      .checked_mul(self.tge_unlock_bps as u128)
+      .unwrap()
+      .checked_div(BASIS_POINTS as u128)
+      .unwrap() as u64;
+    let vesting_start = start + self.cliff;
+    if now < vesting_start {
+      return tge;
+    }
+    if now >= vesting_start + self.period {
+      return total;
+    }
+    let linear = ((total - tge) as u128)
+      .checked_mul((now - vesting_start) as u128)
+      .unwrap()
+      .checked_div(self.period as u128)
+      .unwrap() as u64;
+    tge + linear
+  }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
@@ -26,6 +751,7 @@ pub struct IdoTimes {
   pub start_ido: i64,
   pub end_whitelisted: i64,
   pub end_deposits: i64,
+  pub end_reveal: i64, // Sealed bids are revealed between end_deposits and end_reveal.
   pub end_ido: i64,
 }
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Clone)]
@@ -37,6 +763,21 @@ pub struct PoolBumps {
   pub pool_native: u8,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone)]
+pub struct StakePoolBumps {
+  pub stake_pool: u8,
+  pub stake_vault: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
+pub struct WhitelistTier {
+  pub merkle_root: [u8; 32],
+  pub target_investment: u64, // USDC needed to buy out the whole tier allocation.
+  pub num_ido_tokens: u64,
+  pub sold: u64,
+  pub allocation: TierAllocation,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Clone, Copy)]
 pub struct PoolInfo {
   pub is_initialized: bool,
@@ -1,5 +1,7 @@
 use crate::{account::*, error::ErrorCode};
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::sysvar::{instructions, slot_hashes};
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use std::ops::Deref;
 
@@ -14,12 +16,180 @@ const DECIMALS: u8 = 6;
 
 
 
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct InitLaunchpad<'info> {
+  #[account(mut)]
+  pub admin: Signer<'info>,
+
+  #[account(init,
+    seeds = [b"launchpad_config".as_ref()],
+    bump = bump,
+    payer = admin
+  )]
+  pub launchpad_config: Account<'info, LaunchpadConfig>,
+
+  // Only the program's upgrade authority can claim the launchpad.
+  #[account(
+    address = program_data_address() @ ErrorCode::Unauthorized,
+    constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized
+  )]
+  pub program_data: Account<'info, ProgramData>,
+
+  pub system_program : Program<'info, System>,
+}
+
+
+#[derive(Accounts)]
+pub struct UpdateLaunchpad<'info> {
+  pub admin: Signer<'info>,
+
+  #[account(mut,
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump,
+    constraint = launchpad_config.admin == admin.key() @ ErrorCode::Unauthorized
+  )]
+  pub launchpad_config: Account<'info, LaunchpadConfig>,
+}
+
+
+#[derive(Accounts)]
+pub struct ReviewIdo<'info> {
+  pub admin: Signer<'info>,
+
+  #[account(
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump,
+    constraint = launchpad_config.admin == admin.key() @ ErrorCode::Unauthorized
+  )]
+  pub launchpad_config: Account<'info, LaunchpadConfig>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account: Account<'info, IdoAccount>,
+}
+
+// The redeemable mint can't be closed, it stays behind for a pool registered
+// again under the same name. Accounts that outlive the pool, like stake snapshots,
+// are derived from its registry index so the next pool doesn't pick them up.
+#[derive(Accounts)]
+pub struct CloseRejectedIdo<'info> {
+  pub admin: Signer<'info>,
+
+  #[account(
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump,
+    constraint = launchpad_config.admin == admin.key() @ ErrorCode::Unauthorized
+  )]
+  pub launchpad_config: Box<Account<'info, LaunchpadConfig>>,
+
+  #[account(mut,
+    constraint = ido_authority.key() == ido_account.ido_authority
+  )]
+  pub ido_authority: AccountInfo<'info>,
+
+  #[account(mut,
+    constraint = ido_authority_watermelon.owner == ido_account.ido_authority,
+    constraint = ido_authority_watermelon.mint == ido_account.watermelon_mint
+  )]
+  pub ido_authority_watermelon: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = ido_authority_usdc.owner == ido_account.ido_authority,
+    constraint = ido_authority_usdc.mint == ido_account.usdc_mint
+  )]
+  pub ido_authority_usdc: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account,
+    close = ido_authority
+  )]
+  pub ido_account: Box<Account<'info, IdoAccount>>,
+
+  #[account(mut,
+    seeds = [b"ido_registry".as_ref(), ido_account.registry_index.to_le_bytes().as_ref()],
+    bump,
+    constraint = registry_entry.ido_account == ido_account.key()
+  )]
+  pub registry_entry: Box<Account<'info, IdoRegistryEntry>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_watermelon"],
+    bump = ido_account.bumps.pool_watermelon)]
+  pub pool_watermelon: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc"],
+    bump = ido_account.bumps.pool_usdc)]
+  pub pool_usdc: Box<Account<'info, TokenAccount>>,
+
+  pub token_program: Program<'info, Token>,
+}
+
+
+// The ido authority or the launchpad admin can pause a pool.
+#[derive(Accounts)]
+pub struct PausePool<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump
+  )]
+  pub launchpad_config: Account<'info, LaunchpadConfig>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account,
+    constraint = ido_account.ido_authority == authority.key()
+      || launchpad_config.admin == authority.key() @ ErrorCode::Unauthorized
+  )]
+  pub ido_account: Account<'info, IdoAccount>,
+}
+
+
+// Like pausing, the ido authority or the launchpad admin can cancel an ido.
+#[derive(Accounts)]
+pub struct CancelIdo<'info> {
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump
+  )]
+  pub launchpad_config: Account<'info, LaunchpadConfig>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account,
+    constraint = ido_account.ido_authority == authority.key()
+      || launchpad_config.admin == authority.key() @ ErrorCode::Unauthorized
+  )]
+  pub ido_account: Account<'info, IdoAccount>,
+}
+
 #[derive(Accounts)]
 #[instruction(ido_name: String, bumps : PoolBumps)] 
 pub struct InitializePool<'info> {
   #[account(mut)]
   pub ido_authority: Signer<'info>,
 
+  #[account(mut,
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump
+  )]
+  pub launchpad_config: Box<Account<'info, LaunchpadConfig>>,
+
+  #[account(init,
+    seeds = [b"ido_registry".as_ref(), launchpad_config.num_idos.to_le_bytes().as_ref()],
+    bump,
+    payer = ido_authority
+  )]
+  pub registry_entry: Box<Account<'info, IdoRegistryEntry>>,
+
   #[account(mut,
     constraint = ido_authority_watermelon.owner == ido_authority.key(),
     constraint = ido_authority_watermelon.mint == watermelon_mint.key()
@@ -40,12 +210,14 @@ pub struct InitializePool<'info> {
   pub usdc_mint : Box<Account<'info,Mint>>,
 
 
-  #[account(init,
+  // Left over when a rejected pool of the same name was closed.
+  #[account(init_if_needed,
     mint::decimals = DECIMALS,
     mint::authority = ido_account,
     seeds = [ido_name.as_bytes(), b"redeemable_mint".as_ref()],
     bump = bumps.redeemable_mint,
-    payer = ido_authority
+    payer = ido_authority,
+    constraint = redeemable_mint.supply == 0
   )]
   pub redeemable_mint : Box<Account<'info, Mint>>,
 
@@ -115,15 +287,39 @@ pub struct InitUserRedeemable<'info> {
   pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct InitVesting<'info> {
+  #[account(mut)]
+  pub user_authority: Signer<'info>,
+
+  #[account(
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account: Box<Account<'info, IdoAccount>>,
+
+  #[account(init,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"vesting".as_ref()],
+    bump,
+    payer = user_authority
+  )]
+  pub vesting: Box<Account<'info, Vesting>>,
+
+  pub system_program: Program<'info, System>,
+}
+
 
 #[derive(Accounts)]
+#[instruction(tier: u8)]
 pub struct ExchangeUsdcForWaterMelon<'info> {
 
   #[account(mut)]
   pub user_authority : Signer<'info>,
 
 
-  #[account(
+  #[account(mut,
     seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
     bump = ido_account.bumps.ido_account
   )]
@@ -167,7 +363,26 @@ pub struct ExchangeUsdcForWaterMelon<'info> {
   )]
   pub pool_watermelon : Box<Account<'info, TokenAccount>>,
 
+  #[account(init_if_needed,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"whitelist_ledger".as_ref(),
+      &[tier]],
+    bump,
+    payer = user_authority
+  )]
+  pub whitelist_ledger : Box<Account<'info, WhitelistLedger>>,
+
+  // Created by `init_vesting`, only loaded when the private round vests.
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"vesting".as_ref()],
+    bump
+  )]
+  pub vesting : AccountInfo<'info>,
 
+  pub system_program: Program<'info, System>,
   pub token_program: Program<'info, Token>,
 
 
@@ -175,8 +390,25 @@ pub struct ExchangeUsdcForWaterMelon<'info> {
 
 
 #[derive(Accounts)]
-pub struct ExchangeUsdcForRedeemable<'info> {
-  pub user_authority: Signer<'info>,
+pub struct ExchangeUsdcForWatermelonStaked<'info> {
+  pub purchase: ExchangeUsdcForWaterMelon<'info>,
+
+  #[account(
+    seeds = [purchase.user_authority.key().as_ref(),
+      purchase.ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      purchase.ido_account.registry_index.to_le_bytes().as_ref(),
+      b"stake_snapshot".as_ref()],
+    bump,
+    constraint = stake_snapshot.ido_account == purchase.ido_account.key()
+  )]
+  pub stake_snapshot : Box<Account<'info, StakeSnapshot>>,
+}
+
+
+#[derive(Accounts)]
+pub struct RegisterTicket<'info> {
+  #[account(mut)]
+  pub user_authority : Signer<'info>,
 
   #[account(mut,
     seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
@@ -184,96 +416,723 @@ pub struct ExchangeUsdcForRedeemable<'info> {
   )]
   pub ido_account : Account<'info, IdoAccount>,
 
+  #[account(init,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"lottery_ticket".as_ref()],
+    bump,
+    payer = user_authority
+  )]
+  pub lottery_ticket : Box<Account<'info, LotteryTicket>>,
+
+  pub system_program : Program<'info, System>,
+}
+
+
+#[derive(Accounts)]
+pub struct CloseLottery<'info> {
   #[account(mut,
-    constraint = user_usdc.owner == user_authority.key() @ ErrorCode::A ,
-    constraint = user_usdc.mint == usdc_mint.key() @ ErrorCode::B
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
   )]
-  pub user_usdc : Account<'info, TokenAccount>,
+  pub ido_account: Account<'info, IdoAccount>,
+}
+
+
+#[derive(Accounts)]
+pub struct DrawLottery<'info> {
+  pub ido_authority: Signer<'info>,
 
   #[account(mut,
-    seeds = [user_authority.key().as_ref(),
-        ido_account.ido_name.as_ref().trim_ascii_whitespace(),
-        b"user_redeemable"],
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account,
+    constraint = ido_account.ido_authority == ido_authority.key() @ ErrorCode::Unauthorized
+  )]
+  pub ido_account: Account<'info, IdoAccount>,
+
+  // Read for the hash of the slot the lottery closed in.
+  #[account(address = slot_hashes::ID)]
+  pub slot_hashes: AccountInfo<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct ExchangeUsdcForWatermelonLottery<'info> {
+  pub purchase: ExchangeUsdcForWaterMelon<'info>,
+
+  #[account(
+    seeds = [purchase.user_authority.key().as_ref(),
+      purchase.ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"lottery_ticket".as_ref()],
     bump
   )]
-  pub user_redeemable : Account<'info, TokenAccount>,
+  pub lottery_ticket : Box<Account<'info, LotteryTicket>>,
+}
 
 
-  #[account(mut, 
-    seeds = [
-      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
-      b"pool_usdc".as_ref()
-    ],
-    bump = ido_account.bumps.pool_usdc
+#[derive(Accounts)]
+#[instruction(bumps: StakePoolBumps)]
+pub struct InitializeStakePool<'info> {
+  #[account(mut)]
+  pub admin: Signer<'info>,
+
+  // The stake pool is a singleton, only the launchpad admin can create it.
+  #[account(
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump,
+    constraint = launchpad_config.admin == admin.key() @ ErrorCode::Unauthorized
   )]
-  pub pool_usdc : Account<'info, TokenAccount>,
-  
-  #[account(mut,
-    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint"],
-    bump = ido_account.bumps.redeemable_mint
+  pub launchpad_config: Account<'info, LaunchpadConfig>,
+
+  #[account(init,
+    seeds = [b"stake_pool".as_ref()],
+    bump = bumps.stake_pool,
+    payer = admin
   )]
-  pub redeemable_mint: Box<Account<'info, Mint>>,
+  pub stake_pool: Account<'info, StakePool>,
 
-  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint @ ErrorCode::F)]
-  pub usdc_mint : Box<Account<'info, Mint>>,
+  pub stake_mint : Box<Account<'info, Mint>>,
 
-  pub token_program : Program<'info, Token>
+  #[account(init,
+    token::mint = stake_mint,
+    token::authority = stake_pool,
+    seeds = [b"stake_vault".as_ref()],
+    bump = bumps.stake_vault,
+    payer = admin
+  )]
+  pub stake_vault: Account<'info, TokenAccount>,
+
+  pub system_program : Program<'info, System>,
+  pub token_program : Program<'info, Token>,
+  pub rent: Sysvar<'info, Rent>,
 }
 
+
 #[derive(Accounts)]
-pub struct ExchangeRedeemableForWatermelon<'info> {
+pub struct Stake<'info> {
   #[account(mut)]
   pub user_authority : Signer<'info>,
 
-
   #[account(mut,
-    seeds = [
-      ido_account.ido_name.as_ref().trim_ascii_whitespace()
-    ],
-    bump = ido_account.bumps.ido_account
+    seeds = [b"stake_pool".as_ref()],
+    bump = stake_pool.bumps.stake_pool
   )]
-  pub ido_account : Account<'info, IdoAccount>,
+  pub stake_pool: Account<'info, StakePool>,
 
+  #[account(init_if_needed,
+    seeds = [user_authority.key().as_ref(), b"stake_account".as_ref()],
+    bump,
+    payer = user_authority
+  )]
+  pub stake_account : Box<Account<'info, StakeAccount>>,
 
   #[account(mut,
-    seeds = [
-      ido_account.ido_name.as_ref().trim_ascii_whitespace(), 
-      b"pool_watermelon".as_ref()
-    ],
-    bump = ido_account.bumps.pool_watermelon
+    constraint = user_stake.owner == user_authority.key(),
+    constraint = user_stake.mint == stake_pool.stake_mint
   )]
-  pub pool_watermelon : Account<'info, TokenAccount>,
+  pub user_stake : Account<'info, TokenAccount>,
 
   #[account(mut,
-    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint".as_ref()],
-    bump = ido_account.bumps.redeemable_mint
+    seeds = [b"stake_vault".as_ref()],
+    bump = stake_pool.bumps.stake_vault
   )]
-  pub redeemable_mint: Box<Account<'info, Mint>>,
+  pub stake_vault: Account<'info, TokenAccount>,
+
+  pub system_program : Program<'info, System>,
+  pub token_program : Program<'info, Token>,
+}
 
 
-  #[account(constraint = watermelon_mint.key() == ido_account.watermelon_mint)]
-  pub watermelon_mint : Box<Account<'info, Mint>>,
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+  pub user_authority : Signer<'info>,
 
   #[account(mut,
-    seeds = [user_authority.key().as_ref(),
-        ido_account.ido_name.as_ref().trim_ascii_whitespace(),
-        b"user_redeemable".as_ref()],
+    seeds = [b"stake_pool".as_ref()],
+    bump = stake_pool.bumps.stake_pool
+  )]
+  pub stake_pool: Account<'info, StakePool>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(), b"stake_account".as_ref()],
     bump
   )]
-  pub user_redeemable: Account<'info, TokenAccount>,
+  pub stake_account : Box<Account<'info, StakeAccount>>,
 
   #[account(mut,
-    constraint = user_watermelon.owner == user_authority.key(),
-    constraint = user_watermelon.mint == watermelon_mint.key()
+    constraint = user_stake.owner == user_authority.key(),
+    constraint = user_stake.mint == stake_pool.stake_mint
   )]
-  pub user_watermelon: Account<'info, TokenAccount>,
+  pub user_stake : Account<'info, TokenAccount>,
 
+  #[account(mut,
+    seeds = [b"stake_vault".as_ref()],
+    bump = stake_pool.bumps.stake_vault
+  )]
+  pub stake_vault: Account<'info, TokenAccount>,
 
+  pub token_program : Program<'info, Token>,
+}
 
-  pub token_program : Program<'info, Token>
 
+#[derive(Accounts)]
+pub struct SnapshotStake<'info> {
+  #[account(mut)]
+  pub user_authority : Signer<'info>,
 
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(), b"stake_account".as_ref()],
+    bump
+  )]
+  pub stake_account : Box<Account<'info, StakeAccount>>,
+
+  #[account(init_if_needed,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      ido_account.registry_index.to_le_bytes().as_ref(),
+      b"stake_snapshot".as_ref()],
+    bump,
+    payer = user_authority
+  )]
+  pub stake_snapshot : Box<Account<'info, StakeSnapshot>>,
+
+  pub system_program : Program<'info, System>,
+}
+
+
+#[derive(Accounts)]
+pub struct ExchangeUsdcForWatermelonSigned<'info> {
+  pub purchase: ExchangeUsdcForWaterMelon<'info>,
+
+  // Read to find the Ed25519 instruction that checked the allowlist signature.
+  #[account(address = instructions::ID)]
+  pub instructions : AccountInfo<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct UpdateAllowlistSigner<'info> {
+  pub ido_authority: Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account,
+    constraint = ido_account.ido_authority == ido_authority.key() @ ErrorCode::Unauthorized
+  )]
+  pub ido_account: Account<'info, IdoAccount>,
+}
+
+
+#[derive(Accounts)]
+pub struct UpdateMerkleRoot<'info> {
+  pub ido_authority: Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account,
+    constraint = ido_account.ido_authority == ido_authority.key() @ ErrorCode::Unauthorized
+  )]
+  pub ido_account: Account<'info, IdoAccount>,
+}
+
+
+#[derive(Accounts)]
+pub struct ExchangeUsdcForRedeemable<'info> {
+  #[account(mut)]
+  pub user_authority: Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key() @ ErrorCode::A ,
+    constraint = user_usdc.mint == usdc_mint.key() @ ErrorCode::B
+  )]
+  pub user_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+        ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+        b"user_redeemable"],
+    bump
+  )]
+  pub user_redeemable : Account<'info, TokenAccount>,
+
+
+  #[account(mut, 
+    seeds = [
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"pool_usdc".as_ref()
+    ],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Account<'info, TokenAccount>,
+  
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint"],
+    bump = ido_account.bumps.redeemable_mint
+  )]
+  pub redeemable_mint: Box<Account<'info, Mint>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint @ ErrorCode::F)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  #[account(init_if_needed,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"deposit_ledger".as_ref()],
+    bump,
+    payer = user_authority
+  )]
+  pub deposit_ledger : Box<Account<'info, DepositLedger>>,
+
+  pub system_program: Program<'info, System>,
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct ExchangeRedeemableForUsdc<'info> {
+  pub user_authority: Signer<'info>,
+
+  #[account(
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == usdc_mint.key()
+  )]
+  pub user_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+        ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+        b"user_redeemable"],
+    bump
+  )]
+  pub user_redeemable : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint"],
+    bump = ido_account.bumps.redeemable_mint
+  )]
+  pub redeemable_mint: Box<Account<'info, Mint>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"deposit_ledger".as_ref()],
+    bump
+  )]
+  pub deposit_ledger : Box<Account<'info, DepositLedger>>,
+
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct ExchangeRedeemableForWatermelon<'info> {
+  #[account(mut)]
+  pub user_authority : Signer<'info>,
+
+
+  #[account(mut,
+    seeds = [
+      ido_account.ido_name.as_ref().trim_ascii_whitespace()
+    ],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+
+  #[account(mut,
+    seeds = [
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(), 
+      b"pool_watermelon".as_ref()
+    ],
+    bump = ido_account.bumps.pool_watermelon
+  )]
+  pub pool_watermelon : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint".as_ref()],
+    bump = ido_account.bumps.redeemable_mint
+  )]
+  pub redeemable_mint: Box<Account<'info, Mint>>,
+
+
+  #[account(constraint = watermelon_mint.key() == ido_account.watermelon_mint)]
+  pub watermelon_mint : Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+        ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+        b"user_redeemable".as_ref()],
+    bump
+  )]
+  pub user_redeemable: Account<'info, TokenAccount>,
+
+  #[account(mut,
+    constraint = user_watermelon.owner == user_authority.key(),
+    constraint = user_watermelon.mint == watermelon_mint.key()
+  )]
+  pub user_watermelon: Account<'info, TokenAccount>,
+
+  // Receives the USDC refunded by an oversubscribed overflow sale.
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == usdc_mint.key()
+  )]
+  pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc: Box<Account<'info, TokenAccount>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  // Holds what a dutch auction bidder bid for.
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"deposit_ledger".as_ref()],
+    bump
+  )]
+  pub deposit_ledger : Box<Account<'info, DepositLedger>>,
+
+  // Created by `init_vesting`, only loaded when the public sale vests.
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"vesting".as_ref()],
+    bump
+  )]
+  pub vesting : AccountInfo<'info>,
+
+  pub token_program : Program<'info, Token>
+
+
+
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == usdc_mint.key()
+  )]
+  pub user_usdc : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = user_watermelon.owner == user_authority.key(),
+    constraint = user_watermelon.mint == watermelon_mint.key()
+  )]
+  pub user_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_watermelon".as_ref()],
+    bump = ido_account.bumps.pool_watermelon
+  )]
+  pub pool_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  #[account(constraint = watermelon_mint.key() == ido_account.watermelon_mint)]
+  pub watermelon_mint : Box<Account<'info, Mint>>,
+
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+  #[account(mut)]
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(init,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"bid".as_ref()],
+    bump,
+    payer = user_authority
+  )]
+  pub bid : Box<Account<'info, Bid>>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == usdc_mint.key()
+  )]
+  pub user_usdc : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Box<Account<'info, TokenAccount>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  pub system_program: Program<'info, System>,
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"bid".as_ref()],
+    bump
+  )]
+  pub bid : Box<Account<'info, Bid>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBid<'info> {
+  #[account(mut)]
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"bid".as_ref()],
+    bump,
+    close = user_authority
+  )]
+  pub bid : Box<Account<'info, Bid>>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == usdc_mint.key()
+  )]
+  pub user_usdc : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = user_watermelon.owner == user_authority.key(),
+    constraint = user_watermelon.mint == watermelon_mint.key()
+  )]
+  pub user_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_watermelon".as_ref()],
+    bump = ido_account.bumps.pool_watermelon
+  )]
+  pub pool_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  #[account(constraint = watermelon_mint.key() == ido_account.watermelon_mint)]
+  pub watermelon_mint : Box<Account<'info, Mint>>,
+
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"vesting".as_ref()],
+    bump
+  )]
+  pub vesting : Box<Account<'info, Vesting>>,
+
+  #[account(mut,
+    constraint = user_watermelon.owner == user_authority.key(),
+    constraint = user_watermelon.mint == watermelon_mint.key()
+  )]
+  pub user_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_watermelon".as_ref()],
+    bump = ido_account.bumps.pool_watermelon
+  )]
+  pub pool_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(constraint = watermelon_mint.key() == ido_account.watermelon_mint)]
+  pub watermelon_mint : Box<Account<'info, Mint>>,
+
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct RefundRedeemable<'info> {
+  #[account(mut)]
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == usdc_mint.key()
+  )]
+  pub user_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+        ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+        b"user_redeemable"],
+    bump
+  )]
+  pub user_redeemable : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint"],
+    bump = ido_account.bumps.redeemable_mint
+  )]
+  pub redeemable_mint: Box<Account<'info, Mint>>,
+
+  #[account(constraint = usdc_mint.key() == ido_account.usdc_mint)]
+  pub usdc_mint : Box<Account<'info, Mint>>,
+
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct RefundPrivatePurchase<'info> {
+  pub user_authority : Signer<'info>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    constraint = user_usdc.owner == user_authority.key(),
+    constraint = user_usdc.mint == ido_account.usdc_mint
+  )]
+  pub user_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    constraint = user_watermelon.owner == user_authority.key(),
+    constraint = user_watermelon.mint == ido_account.watermelon_mint
+  )]
+  pub user_watermelon : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_usdc".as_ref()],
+    bump = ido_account.bumps.pool_usdc
+  )]
+  pub pool_usdc : Account<'info, TokenAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_watermelon".as_ref()],
+    bump = ido_account.bumps.pool_watermelon
+  )]
+  pub pool_watermelon : Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"whitelist_ledger".as_ref(),
+      &[tier]],
+    bump
+  )]
+  pub whitelist_ledger : Box<Account<'info, WhitelistLedger>>,
+
+  // Created by `init_vesting`, only loaded when the private round vests.
+  #[account(mut,
+    seeds = [user_authority.key().as_ref(),
+      ido_account.ido_name.as_ref().trim_ascii_whitespace(),
+      b"vesting".as_ref()],
+    bump
+  )]
+  pub vesting : AccountInfo<'info>,
 
+  pub token_program : Program<'info, Token>
 }
 
 #[derive(Accounts)]
@@ -293,7 +1152,7 @@ pub struct WithdrawPoolUsdc<'info> {
   pub user_usdc: Box<Account<'info, TokenAccount>>,
 
 
-  #[account(
+  #[account(mut,
     seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
     bump = ido_account.bumps.ido_account
   )]
@@ -304,14 +1163,74 @@ pub struct WithdrawPoolUsdc<'info> {
     bump = ido_account.bumps.pool_usdc)]
   pub pool_usdc: Box<Account<'info, TokenAccount>>,
 
+  #[account(
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint"],
+    bump = ido_account.bumps.redeemable_mint)]
+  pub redeemable_mint: Box<Account<'info, Mint>>,
+
   #[account(
     constraint = ido_account.usdc_mint == usdc_mint.key()
   )]
   pub usdc_mint: Box<Account<'info, Mint>>,
 
+  #[account(
+    seeds = [b"launchpad_config".as_ref()],
+    bump = launchpad_config.bump
+  )]
+  pub launchpad_config: Box<Account<'info, LaunchpadConfig>>,
+
+  #[account(mut,
+    constraint = treasury_usdc.owner == launchpad_config.treasury,
+    constraint = treasury_usdc.mint == usdc_mint.key())]
+  pub treasury_usdc: Box<Account<'info, TokenAccount>>,
+
+  pub token_program : Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnsoldWatermelon<'info> {
+  // Like `WithdrawPoolUsdc`, anyone can send the unsold tokens back to the ido authority.
+  pub payer : Signer<'info>,
+
+  #[account(
+    constraint = user_authority.key() == ido_account.ido_authority
+  )]
+  pub user_authority : AccountInfo<'info>,
+
+  #[account(mut,
+    constraint = user_watermelon.owner == user_authority.key(),
+    constraint = user_watermelon.mint == watermelon_mint.key())]
+  pub user_watermelon: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace()],
+    bump = ido_account.bumps.ido_account
+  )]
+  pub ido_account : Account<'info, IdoAccount>,
+
+  #[account(mut,
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"pool_watermelon"],
+    bump = ido_account.bumps.pool_watermelon)]
+  pub pool_watermelon: Box<Account<'info, TokenAccount>>,
+
+  #[account(
+    seeds = [ido_account.ido_name.as_ref().trim_ascii_whitespace(), b"redeemable_mint"],
+    bump = ido_account.bumps.redeemable_mint)]
+  pub redeemable_mint: Box<Account<'info, Mint>>,
+
+  #[account(
+    constraint = ido_account.watermelon_mint == watermelon_mint.key()
+  )]
+  pub watermelon_mint: Box<Account<'info, Mint>>,
+
   pub token_program : Program<'info, Token>
 }
 
+/// Address of the account the upgradeable loader keeps this program's upgrade authority in.
+pub fn program_data_address() -> Pubkey {
+  Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
 /// Trait to allow trimming ascii whitespace from a &[u8].
 pub trait TrimAsciiWhitespace {
   /// Trim ascii whitespace (based on `is_ascii_whitespace()`) from the
@@ -0,0 +1,45 @@
+use anchor_lang::solana_program::{ed25519_program, instruction::Instruction, pubkey::Pubkey};
+
+// Layout of an Ed25519 program instruction: a count and a padding byte, then
+// one offsets struct per signature.
+const HEADER_LEN: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+// Offsets pointing at this index refer to the Ed25519 instruction's own data.
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+pub struct Ed25519 {}
+
+impl Ed25519 {
+  // The Ed25519 program fails the transaction if the signature is wrong, so
+  // here we only check that it verified `message` signed by `signer`.
+  pub fn verify(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> bool {
+    if ix.program_id != ed25519_program::id() || !ix.accounts.is_empty() {
+      return false;
+    }
+    let data = &ix.data;
+    if data.len() < HEADER_LEN + OFFSETS_LEN || data[0] != 1 {
+      return false;
+    }
+
+    let offset = |i: usize| {
+      let at = HEADER_LEN + i * 2;
+      u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    let signature_offset = offset(0) as usize;
+    let public_key_offset = offset(2) as usize;
+    let message_offset = offset(4) as usize;
+    let message_size = offset(5) as usize;
+    if offset(1) != CURRENT_INSTRUCTION
+      || offset(3) != CURRENT_INSTRUCTION
+      || offset(6) != CURRENT_INSTRUCTION
+    {
+      return false;
+    }
+
+    signature_offset + SIGNATURE_LEN <= data.len()
+      && data.get(public_key_offset..public_key_offset + PUBKEY_LEN) == Some(signer.as_ref())
+      && data.get(message_offset..message_offset + message_size) == Some(message)
+  }
+}
@@ -42,6 +42,122 @@ pub enum ErrorCode {
   InvalidAmountOut,
   #[msg("Amount paid is invalid")]
   InvalidAmountPaid,
+  #[msg("Whitelist allocation exceeded")]
+  AllocationExceeded,
+  #[msg("Given whitelist tier is invalid")]
+  InvalidTier,
+  #[msg("Too many whitelist tiers")]
+  TooManyTiers,
+  #[msg("Whitelist tier is sold out")]
+  TierSoldOut,
+  #[msg("No unsold tokens to withdraw")]
+  NothingToWithdraw,
+  #[msg("Private allocation is sold out")]
+  PrivateSoldOut,
+  #[msg("Soft cap was not reached")]
+  SoftCapNotReached,
+  #[msg("Soft cap was reached")]
+  SoftCapReached,
+  #[msg("Hard cap reached")]
+  HardCapReached,
+  #[msg("Soft cap is above hard cap")]
+  InvalidCaps,
+  #[msg("Deposit is below the minimum contribution")]
+  BelowMinContribution,
+  #[msg("Deposit exceeds the maximum contribution")]
+  AboveMaxContribution,
+  #[msg("Minimum contribution is above maximum contribution")]
+  InvalidContributionLimits,
+  #[msg("Sale mode is misconfigured")]
+  InvalidSaleConfig,
+  #[msg("Public allocation is sold out")]
+  PublicSoldOut,
+  #[msg("Bid is too small to buy any tokens")]
+  BidTooSmall,
+  #[msg("Auction bids can't be withdrawn")]
+  AuctionBidFinal,
+  #[msg("Not available in this sale mode")]
+  InvalidSaleMode,
+  #[msg("Swap output is below the minimum")]
+  SlippageExceeded,
+  #[msg("Swap is over half the pool's USDC balance")]
+  SwapTooLarge,
+  #[msg("Reveal period has ended")]
+  EndReveal,
+  #[msg("Reveal period has not ended")]
+  RevealNotOver,
+  #[msg("Revealed bid doesn't match the commitment")]
+  InvalidReveal,
+  #[msg("Bid price is not on the price grid")]
+  InvalidBidPrice,
+  #[msg("Bid is not covered by its escrow")]
+  BidUnderfunded,
+  #[msg("Auction has not been settled")]
+  AuctionNotSettled,
+  #[msg("Auction is already settled")]
+  AuctionSettled,
+  #[msg("Vesting schedule is invalid")]
+  InvalidVesting,
+  #[msg("No vested tokens to claim")]
+  NothingVested,
+  #[msg("Stake amount or lock period is invalid")]
+  InvalidStake,
+  #[msg("Stake is still locked")]
+  StakeLocked,
+  #[msg("Not enough stake to withdraw")]
+  InsufficientStake,
+  #[msg("Stake registration is closed once the IDO starts")]
+  StakeRegistrationClosed,
+  #[msg("Lottery config is invalid")]
+  InvalidLottery,
+  #[msg("Lottery is already drawn")]
+  LotteryDrawn,
+  #[msg("Lottery is not drawn yet")]
+  LotteryNotDrawn,
+  #[msg("Lottery registration is closed")]
+  LotteryRegistrationClosed,
+  #[msg("Lottery registration is still open")]
+  LotteryRegistrationOpen,
+  #[msg("Lottery is already closed")]
+  LotteryClosed,
+  #[msg("Lottery is not closed yet")]
+  LotteryNotClosed,
+  #[msg("Lottery draw deadline has passed")]
+  LotteryDrawExpired,
+  #[msg("Closing slot hash is no longer available")]
+  LotterySlotUnavailable,
+  #[msg("Seed doesn't match the committed hash")]
+  InvalidSeed,
+  #[msg("Ticket did not win the lottery")]
+  NotALotteryWinner,
+  #[msg("Allowlist signature is missing or invalid")]
+  InvalidSignature,
+  #[msg("Allowlist signature has expired")]
+  AllowlistExpired,
+  #[msg("Launchpad config is invalid")]
+  InvalidLaunchpadConfig,
+  #[msg("Quote mint is not allowed by the launchpad")]
+  QuoteMintNotAllowed,
+  #[msg("IDO is not approved by the launchpad")]
+  IdoNotApproved,
+  #[msg("IDO has already been reviewed")]
+  IdoReviewed,
+  #[msg("IDO has not been rejected")]
+  IdoNotRejected,
+  #[msg("IDO is paused")]
+  IdoPaused,
+  #[msg("Launchpad is paused")]
+  LaunchpadPaused,
+  #[msg("IDO is cancelled")]
+  IdoCancelled,
+  #[msg("IDO is not cancelled")]
+  IdoNotCancelled,
+  #[msg("Nothing to refund")]
+  NothingToRefund,
+  #[msg("Pool accounting doesn't add up")]
+  MathOverflow,
+  #[msg("IDO has no vesting schedule")]
+  NoVestingSchedule,
 
   // DEBUG
   #[msg("A")]
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct MerkleRootUpdated {
+  pub ido_account: Pubkey,
+  pub tier: u8,
+  pub old_root: [u8; 32],
+  pub new_root: [u8; 32],
+}
+
+#[event]
+pub struct AuctionSettled {
+  pub ido_account: Pubkey,
+  pub clearing_price: u64,
+  pub sold: u64,
+}
+
+#[event]
+pub struct LotteryDrawn {
+  pub ido_account: Pubkey,
+  pub seed: [u8; 32],
+  pub slot: u64,
+  pub tickets: u64,
+  pub offset: u64,
+}
+
+#[event]
+pub struct AllowlistSignerUpdated {
+  pub ido_account: Pubkey,
+  pub old_signer: Pubkey,
+  pub new_signer: Pubkey,
+}
+
+#[event]
+pub struct IdoReviewed {
+  pub ido_account: Pubkey,
+  pub approved: bool,
+}
+
+#[event]
+pub struct PoolPaused {
+  pub ido_account: Pubkey,
+  pub authority: Pubkey,
+  pub paused: bool,
+}
+
+#[event]
+pub struct IdoCancelled {
+  pub ido_account: Pubkey,
+  pub authority: Pubkey,
+}
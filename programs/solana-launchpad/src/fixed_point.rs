@@ -0,0 +1,61 @@
+// Fixed-point numbers with 18 decimals, after Balancer's BNum. Prices the LBP
+// swaps in integers, floats are not exact enough to move funds with.
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+// The fractional power stops adding terms once they fall below this, and
+// `pow_up` pads its result by as much to cover what was left out.
+const POW_PRECISION: u128 = ONE / 10_000_000_000;
+
+pub struct FixedPoint {}
+
+impl FixedPoint {
+  pub fn mul(a: u128, b: u128) -> u128 {
+    a.checked_mul(b).unwrap().checked_add(ONE / 2).unwrap() / ONE
+  }
+
+  /// `base^exp` for a base between 2/3 and ONE, where the series converges
+  /// fast. Padded so the result is never below the exact power.
+  pub fn pow_up(base: u128, exp: u128) -> u128 {
+    let whole = Self::powi(base, exp / ONE);
+    let remain = exp % ONE;
+    let pow = if remain == 0 {
+      whole
+    } else {
+      Self::mul(whole, Self::pow_approx(base, remain))
+    };
+    pow.checked_add(POW_PRECISION).unwrap().min(ONE)
+  }
+
+  // Exponentiation by squaring for a whole exponent.
+  fn powi(mut base: u128, mut n: u128) -> u128 {
+    let mut result = if n & 1 == 1 { base } else { ONE };
+    n /= 2;
+    while n != 0 {
+      base = Self::mul(base, base);
+      if n & 1 == 1 {
+        result = Self::mul(result, base);
+      }
+      n /= 2;
+    }
+    result
+  }
+
+  // Binomial series of `(1 - x)^a` for a fractional `a`, with `x = ONE - base`.
+  // Every term after the first is subtracted, each one is the previous times
+  // `(k - 1 - a) * x / k`.
+  fn pow_approx(base: u128, a: u128) -> u128 {
+    let x = ONE - base;
+    let mut term = ONE;
+    let mut sum = ONE;
+    let mut k: u128 = 1;
+    while term >= POW_PRECISION {
+      let c = if k == 1 { a } else { (k - 1) * ONE - a };
+      term = Self::mul(term, Self::mul(c, x)) / k;
+      if term == 0 {
+        break;
+      }
+      sum -= term;
+      k += 1;
+    }
+    sum
+  }
+}
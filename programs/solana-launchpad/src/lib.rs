@@ -1,36 +1,99 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::program_pack::IsInitialized;
+use anchor_lang::solana_program::keccak;
 use anchor_spl::token::{self, Burn, CloseAccount, MintTo, Transfer};
 
 declare_id!("HxaDam53rUz8erQXzLxr2y1qTL1U4uJ7cQRMbXwJTG6t");
 
 use access::*;
-use account::{IdoTimes, PoolBumps};
+use account::{
+    IdoAccount, IdoStatus, IdoTimes, PoolBumps, PoolParams, SaleMode, StakePoolBumps,
+    TierAllocation, Vesting, WhitelistTier,
+};
 use context::*;
 use error::*;
+use event::*;
+use slot_hashes::SlotHashes;
 
 mod access;
 mod account;
 mod context;
+mod ed25519;
 mod error;
+mod event;
+mod fixed_point;
 mod merkle_proof;
+mod slot_hashes;
 
 #[program]
 pub mod solana_launchpad {
     use super::*;
 
-    #[access_control(validate_ido_times(ido_times))]
+    #[access_control(validate_launchpad_config(fee_bps, &quote_mints))]
+    pub fn init_launchpad(
+        ctx: Context<InitLaunchpad>,
+        bump: u8,
+        treasury: Pubkey,
+        fee_bps: u64,
+        quote_mints: Vec<Pubkey>,
+    ) -> ProgramResult {
+        msg!("INIT LAUNCHPAD");
+        let launchpad_config = &mut ctx.accounts.launchpad_config;
+        launchpad_config.admin = ctx.accounts.admin.key();
+        launchpad_config.treasury = treasury;
+        launchpad_config.fee_bps = fee_bps;
+        launchpad_config.set_quote_mints(&quote_mints);
+        launchpad_config.bump = bump;
+        Ok(())
+    }
+
+    #[access_control(validate_launchpad_config(fee_bps, &quote_mints))]
+    pub fn update_launchpad(
+        ctx: Context<UpdateLaunchpad>,
+        admin: Pubkey,
+        treasury: Pubkey,
+        fee_bps: u64,
+        quote_mints: Vec<Pubkey>,
+        paused: bool,
+    ) -> ProgramResult {
+        msg!("UPDATE LAUNCHPAD");
+        let launchpad_config = &mut ctx.accounts.launchpad_config;
+        launchpad_config.admin = admin;
+        launchpad_config.treasury = treasury;
+        launchpad_config.fee_bps = fee_bps;
+        launchpad_config.set_quote_mints(&quote_mints);
+        launchpad_config.paused = paused;
+        Ok(())
+    }
+
+    #[access_control(
+        validate_ido_times(ido_times)
+        validate_caps(params.soft_cap, params.hard_cap)
+        validate_contribution_limits(params.min_contribution, params.max_contribution)
+        validate_sale_mode(params.sale_mode, params.hard_cap, params.fixed_price, params.auction, params.lbp, params.sealed_bid, ido_times)
+        validate_vesting(params.private_vesting, params.public_vesting)
+        validate_whitelist_tiers(&tiers, params.lottery)
+        validate_lottery(params.lottery, ido_times)
+    )]
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
         ido_name: String,
         bumps: PoolBumps,
-        private_target_investment: u64,
         num_ido_tokens_public: u64,
-        num_ido_tokens_private: u64,
+        params: PoolParams,
         ido_times: IdoTimes,
-        merkle_root: Option<[u8; 32]>,
+        tiers: Vec<WhitelistTier>,
     ) -> ProgramResult {
         msg!("INITIALIZE POOL");
+        require!(
+            !ctx.accounts.launchpad_config.paused,
+            ErrorCode::LaunchpadPaused
+        );
+        require!(
+            ctx.accounts
+                .launchpad_config
+                .allows_quote_mint(&ctx.accounts.usdc_mint.key()),
+            ErrorCode::QuoteMintNotAllowed
+        );
         let ido_account = &mut ctx.accounts.ido_account;
 
         let name_bytes = ido_name.as_bytes();
@@ -43,15 +106,47 @@ pub mod solana_launchpad {
         ido_account.watermelon_mint = ctx.accounts.watermelon_mint.key();
         ido_account.pool_watermelon = ctx.accounts.pool_watermelon.key();
         ido_account.num_ido_tokens_public = num_ido_tokens_public;
-        ido_account.num_ido_tokens_private = num_ido_tokens_private;
-        ido_account.private_target_investment = private_target_investment;
+        ido_account.soft_cap = params.soft_cap;
+        ido_account.hard_cap = params.hard_cap;
+        ido_account.min_contribution = params.min_contribution;
+        ido_account.max_contribution = params.max_contribution;
+        ido_account.sale_mode = params.sale_mode;
+        ido_account.fixed_price = params.fixed_price;
+        ido_account.auction = params.auction;
+        ido_account.lbp = params.lbp;
+        ido_account.sealed_bid = params.sealed_bid;
+        ido_account.private_vesting = params.private_vesting;
+        ido_account.public_vesting = params.public_vesting;
+        ido_account.lottery = params.lottery;
+        ido_account.fee_bps = ctx.accounts.launchpad_config.fee_bps;
         // Only for pool token
         ido_account.pool_usdc = ctx.accounts.pool_usdc.key();
         ido_account.usdc_mint = ctx.accounts.usdc_mint.key();
         ido_account.ido_times = ido_times;
-        if let Some(root) = merkle_root {
-            ido_account.merkle_root = root;
+
+        let mut num_ido_tokens_private: u64 = 0;
+        for (i, tier) in tiers.iter().enumerate() {
+            ido_account.tiers[i] = WhitelistTier { sold: 0, ..*tier };
+            num_ido_tokens_private = num_ido_tokens_private
+                .checked_add(tier.num_ido_tokens)
+                .unwrap();
         }
+        ido_account.num_tiers = tiers.len() as u8;
+        ido_account.num_ido_tokens_private = num_ido_tokens_private;
+
+        msg!("REGISTER IDO");
+        let launchpad_config = &mut ctx.accounts.launchpad_config;
+        let registry_entry = &mut ctx.accounts.registry_entry;
+        ido_account.registry_index = launchpad_config.num_idos;
+        registry_entry.index = launchpad_config.num_idos;
+        registry_entry.ido_account = ido_account.key();
+        registry_entry.ido_name = ido_account.ido_name;
+        registry_entry.ido_authority = ido_account.ido_authority;
+        registry_entry.usdc_mint = ido_account.usdc_mint;
+        registry_entry.watermelon_mint = ido_account.watermelon_mint;
+        registry_entry.ido_times = ido_times;
+        launchpad_config.num_idos = launchpad_config.num_idos.checked_add(1).unwrap();
+
         // Transfer Watermelon from ido_authority_watermelon to pool account.
         let cpi_accounts = Transfer {
             from: ctx.accounts.ido_authority_watermelon.to_account_info(),
@@ -67,62 +162,367 @@ pub mod solana_launchpad {
         Ok(())
     }
 
+    // The launchpad admin approves or rejects a pending ido, only approved idos
+    // can open their sale phases.
+    pub fn review_ido(ctx: Context<ReviewIdo>, approved: bool) -> ProgramResult {
+        msg!("REVIEW IDO");
+        let ido_account = &mut ctx.accounts.ido_account;
+        require!(
+            ido_account.status == IdoStatus::Pending,
+            ErrorCode::IdoReviewed
+        );
+        ido_account.status = if approved {
+            IdoStatus::Approved
+        } else {
+            IdoStatus::Rejected
+        };
+
+        emit!(IdoReviewed {
+            ido_account: ido_account.key(),
+            approved,
+        });
+        Ok(())
+    }
+
+    // Winds up a rejected ido: the watermelon goes back to its authority and the
+    // pool accounts are closed, so the name can be registered again.
+    pub fn close_rejected_ido(ctx: Context<CloseRejectedIdo>) -> ProgramResult {
+        msg!("CLOSE REJECTED IDO");
+        require!(
+            ctx.accounts.ido_account.status == IdoStatus::Rejected,
+            ErrorCode::IdoNotRejected
+        );
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        msg!("RETURN WATERMELON");
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_watermelon.to_account_info(),
+            to: ctx.accounts.ido_authority_watermelon.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, ctx.accounts.pool_watermelon.amount)?;
+
+        // Nothing is sold before approval, but anyone can send USDC to the pool
+        // and a token account only closes once empty.
+        if ctx.accounts.pool_usdc.amount > 0 {
+            msg!("RETURN USDC");
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_usdc.to_account_info(),
+                to: ctx.accounts.ido_authority_usdc.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, ctx.accounts.pool_usdc.amount)?;
+        }
+
+        msg!("CLOSE POOL ACCOUNTS");
+        for pool in [
+            ctx.accounts.pool_watermelon.to_account_info(),
+            ctx.accounts.pool_usdc.to_account_info(),
+        ] {
+            let cpi_accounts = CloseAccount {
+                account: pool,
+                destination: ctx.accounts.ido_authority.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::close_account(cpi_ctx)?;
+        }
+
+        ctx.accounts.registry_entry.closed = true;
+        Ok(())
+    }
+
+    pub fn pause_pool(ctx: Context<PausePool>) -> ProgramResult {
+        msg!("PAUSE POOL");
+        set_paused(ctx.accounts, true)
+    }
+
+    pub fn unpause_pool(ctx: Context<PausePool>) -> ProgramResult {
+        msg!("UNPAUSE POOL");
+        set_paused(ctx.accounts, false)
+    }
+
+    // Pulls the ido before it ends: every sale phase closes for good and buyers
+    // are refunded instead of paid out.
+    #[access_control(cancellable(&ctx.accounts.ido_account))]
+    pub fn cancel_ido(ctx: Context<CancelIdo>) -> ProgramResult {
+        msg!("CANCEL IDO");
+        let ido_account = &mut ctx.accounts.ido_account;
+        ido_account.cancelled = true;
+
+        emit!(IdoCancelled {
+            ido_account: ido_account.key(),
+            authority: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
     #[access_control(unrestricted_phase(&ctx.accounts.ido_account))]
     pub fn init_user_redeemable(ctx: Context<InitUserRedeemable>) -> ProgramResult {
         msg!("INIT USER REDEEMABLE");
         Ok(())
     }
 
+    // Only pools with a vesting schedule lock watermelon, so only their buyers
+    // pay for a vesting account.
+    pub fn init_vesting(ctx: Context<InitVesting>) -> ProgramResult {
+        msg!("INIT VESTING");
+        let ido_account = &ctx.accounts.ido_account;
+        require!(
+            ido_account.private_vesting.is_enabled() || ido_account.public_vesting.is_enabled(),
+            ErrorCode::NoVestingSchedule
+        );
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.user_authority = ctx.accounts.user_authority.key();
+        vesting.ido_account = ido_account.key();
+        Ok(())
+    }
+
     #[access_control(whitelisted_phase(&ctx.accounts.ido_account))]
     pub fn exchange_usdc_for_watermelon(
         ctx: Context<ExchangeUsdcForWaterMelon>,
+        tier: u8,
         proof: Vec<[u8; 32]>,
+        max_amount: u64,
         amount_out: u64,
     ) -> ProgramResult {
         msg!("EXCHANGE USDC FOR WATERMELON ( WHITELISTED )");
 
         let ido_account = &ctx.accounts.ido_account;
+        require!(tier < ido_account.num_tiers, ErrorCode::InvalidTier);
+        let whitelist_tier = ido_account.tiers[tier as usize];
+        require!(
+            whitelist_tier.allocation == TierAllocation::Merkle,
+            ErrorCode::InvalidTier
+        );
         only_for_whitelisted(
             proof,
-            ido_account.merkle_root,
-            ctx.accounts.user_authority.key().as_ref(),
+            whitelist_tier.merkle_root,
+            &ctx.accounts.user_authority.key(),
+            max_amount,
         )?;
 
-        let amount_paid = (amount_out as u128)
-            .checked_mul(ido_account.private_target_investment as u128)
-            .unwrap()
-            .checked_div(ido_account.num_ido_tokens_private as u128)
-            .unwrap();
+        buy_private(ctx.accounts, tier, max_amount, amount_out)
+    }
 
-        require!(amount_out > 0, ErrorCode::InvalidAmountOut);
-        require!(amount_paid > 0, ErrorCode::InvalidAmountPaid);
+    // Same purchase as `exchange_usdc_for_watermelon`, for tiers split by stake
+    // weight instead of a merkle list.
+    #[access_control(whitelisted_phase(&ctx.accounts.purchase.ido_account))]
+    pub fn exchange_usdc_for_watermelon_staked(
+        ctx: Context<ExchangeUsdcForWatermelonStaked>,
+        tier: u8,
+        amount_out: u64,
+    ) -> ProgramResult {
+        msg!("EXCHANGE USDC FOR WATERMELON ( STAKED )");
 
-        // Transfer user's USDC to pool USDC account
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_usdc.to_account_info(),
-            to: ctx.accounts.pool_usdc.to_account_info(),
-            authority: ctx.accounts.user_authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let ido_account = &ctx.accounts.purchase.ido_account;
+        require!(tier < ido_account.num_tiers, ErrorCode::InvalidTier);
+        require!(
+            ido_account.tiers[tier as usize].allocation == TierAllocation::StakeWeighted,
+            ErrorCode::InvalidTier
+        );
+        let max_amount = ido_account.stake_allocation(tier, ctx.accounts.stake_snapshot.amount);
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount_paid as u64)?;
+        buy_private(&mut ctx.accounts.purchase, tier, max_amount, amount_out)
+    }
 
-        let ido_name = ido_account.ido_name.as_ref();
-        let seeds = &[
-            ido_name.trim_ascii_whitespace(),
-            &[ido_account.bumps.ido_account],
-        ];
-        let signer = &[&seeds[..]];
-        // Mint Watermelon to user
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.pool_watermelon.to_account_info(),
-            to: ctx.accounts.user_watermelon.to_account_info(),
-            authority: ido_account.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, amount_out)?;
+    #[access_control(whitelisted_phase(&ctx.accounts.ido_account))]
+    pub fn register_ticket(ctx: Context<RegisterTicket>) -> ProgramResult {
+        msg!("REGISTER LOTTERY TICKET");
+        let ido_account = &mut ctx.accounts.ido_account;
+        require!(ido_account.lottery.is_enabled(), ErrorCode::InvalidLottery);
+        require!(
+            Clock::get()?.unix_timestamp < ido_account.lottery.end_registration,
+            ErrorCode::LotteryRegistrationClosed
+        );
+
+        let user_authority = ctx.accounts.user_authority.key();
+        let lottery_ticket = &mut ctx.accounts.lottery_ticket;
+        lottery_ticket.user_authority = user_authority;
+        lottery_ticket.ido_account = ido_account.key();
+        lottery_ticket.number = ido_account.lottery_tickets;
+
+        ido_account.lottery_tickets = ido_account.lottery_tickets.checked_add(1).unwrap();
+        ido_account.lottery_entropy =
+            keccak::hashv(&[&ido_account.lottery_entropy, user_authority.as_ref()]).0;
+        Ok(())
+    }
+
+    // Anyone can close the lottery once registration is over. The hash of the
+    // slot it closes in is not known yet, so it can't be steered by registering.
+    #[access_control(whitelisted_phase(&ctx.accounts.ido_account))]
+    pub fn close_lottery(ctx: Context<CloseLottery>) -> ProgramResult {
+        msg!("CLOSE LOTTERY");
+        let ido_account = &mut ctx.accounts.ido_account;
+        require!(ido_account.lottery.is_enabled(), ErrorCode::InvalidLottery);
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= ido_account.lottery.end_registration,
+            ErrorCode::LotteryRegistrationOpen
+        );
+        require!(ido_account.lottery_slot == 0, ErrorCode::LotteryClosed);
+
+        ido_account.lottery_slot = clock.slot;
+        Ok(())
+    }
+
+    // Reveals the committed seed and mixes it with the registered wallets and
+    // the closing slot hash. The seed was fixed before registration and the
+    // slot hash after it, so the authority can at most skip the draw, which
+    // opens the tier to every ticket at `end_draw`.
+    #[access_control(whitelisted_phase(&ctx.accounts.ido_account))]
+    pub fn draw_lottery(ctx: Context<DrawLottery>, seed: [u8; 32]) -> ProgramResult {
+        msg!("DRAW LOTTERY");
+        let ido_account = &mut ctx.accounts.ido_account;
+        require!(ido_account.lottery.is_enabled(), ErrorCode::InvalidLottery);
+        require!(!ido_account.lottery_drawn, ErrorCode::LotteryDrawn);
+        require!(ido_account.lottery_slot > 0, ErrorCode::LotteryNotClosed);
+        require!(
+            Clock::get()?.unix_timestamp < ido_account.lottery.end_draw,
+            ErrorCode::LotteryDrawExpired
+        );
+        require!(
+            keccak::hash(&seed).0 == ido_account.lottery.seed_hash,
+            ErrorCode::InvalidSeed
+        );
+        let slot_hash = SlotHashes::find(
+            &ctx.accounts.slot_hashes.try_borrow_data()?,
+            ido_account.lottery_slot,
+        )
+        .ok_or(ErrorCode::LotterySlotUnavailable)?;
+
+        let random = keccak::hashv(&[&seed, &ido_account.lottery_entropy, &slot_hash]).0;
+        let mut offset = [0u8; 8];
+        offset.copy_from_slice(&random[..8]);
+        ido_account.lottery_offset = u64::from_le_bytes(offset)
+            .checked_rem(ido_account.lottery_tickets)
+            .unwrap_or(0);
+        ido_account.lottery_drawn = true;
+
+        emit!(LotteryDrawn {
+            ido_account: ido_account.key(),
+            seed,
+            slot: ido_account.lottery_slot,
+            tickets: ido_account.lottery_tickets,
+            offset: ido_account.lottery_offset,
+        });
+        Ok(())
+    }
+
+    // Same purchase as `exchange_usdc_for_watermelon`, for a winning lottery ticket.
+    #[access_control(whitelisted_phase(&ctx.accounts.purchase.ido_account))]
+    pub fn exchange_usdc_for_watermelon_lottery(
+        ctx: Context<ExchangeUsdcForWatermelonLottery>,
+        tier: u8,
+        amount_out: u64,
+    ) -> ProgramResult {
+        msg!("EXCHANGE USDC FOR WATERMELON ( LOTTERY )");
+
+        let ido_account = &ctx.accounts.purchase.ido_account;
+        require!(tier < ido_account.num_tiers, ErrorCode::InvalidTier);
+        require!(
+            ido_account.tiers[tier as usize].allocation == TierAllocation::Lottery,
+            ErrorCode::InvalidTier
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ido_account.lottery_drawn || now >= ido_account.lottery.end_draw,
+            ErrorCode::LotteryNotDrawn
+        );
+        require!(
+            ido_account.lottery_won(tier, ctx.accounts.lottery_ticket.number, now),
+            ErrorCode::NotALotteryWinner
+        );
+        let max_amount = ido_account.lottery.ticket_size;
+
+        buy_private(&mut ctx.accounts.purchase, tier, max_amount, amount_out)
+    }
+
+    // Same purchase as `exchange_usdc_for_watermelon`, with the allowlist entry
+    // signed by the allowlist signer instead of proven against a merkle root. The
+    // transaction must verify the signature with the Ed25519 program right before.
+    #[access_control(whitelisted_phase(&ctx.accounts.purchase.ido_account))]
+    pub fn exchange_usdc_for_watermelon_signed(
+        ctx: Context<ExchangeUsdcForWatermelonSigned>,
+        tier: u8,
+        max_amount: u64,
+        expiry: i64,
+        amount_out: u64,
+    ) -> ProgramResult {
+        msg!("EXCHANGE USDC FOR WATERMELON ( SIGNED )");
+
+        let ido_account = &ctx.accounts.purchase.ido_account;
+        require!(tier < ido_account.num_tiers, ErrorCode::InvalidTier);
+        require!(
+            ido_account.tiers[tier as usize].allocation == TierAllocation::Signature,
+            ErrorCode::InvalidTier
+        );
+        require!(
+            ido_account.allowlist_signer != Pubkey::default(),
+            ErrorCode::InvalidSignature
+        );
+        only_for_signed(
+            &ctx.accounts.instructions,
+            &ido_account.allowlist_signer,
+            &ido_account.key(),
+            &ctx.accounts.purchase.user_authority.key(),
+            tier,
+            max_amount,
+            expiry,
+        )?;
+
+        buy_private(&mut ctx.accounts.purchase, tier, max_amount, amount_out)
+    }
+
+    #[access_control(whitelist_editable(&ctx.accounts.ido_account))]
+    pub fn update_allowlist_signer(
+        ctx: Context<UpdateAllowlistSigner>,
+        allowlist_signer: Pubkey,
+    ) -> ProgramResult {
+        msg!("UPDATE ALLOWLIST SIGNER");
+        let ido_account = &mut ctx.accounts.ido_account;
+        let old_signer = ido_account.allowlist_signer;
+        ido_account.allowlist_signer = allowlist_signer;
+
+        emit!(AllowlistSignerUpdated {
+            ido_account: ido_account.key(),
+            old_signer,
+            new_signer: allowlist_signer,
+        });
+        Ok(())
+    }
+
+    #[access_control(whitelist_editable(&ctx.accounts.ido_account))]
+    pub fn update_merkle_root(
+        ctx: Context<UpdateMerkleRoot>,
+        tier: u8,
+        merkle_root: [u8; 32],
+    ) -> ProgramResult {
+        msg!("UPDATE MERKLE ROOT");
+        let ido_account = &mut ctx.accounts.ido_account;
+        require!(tier < ido_account.num_tiers, ErrorCode::InvalidTier);
+        let whitelist_tier = &mut ido_account.tiers[tier as usize];
+        let old_root = whitelist_tier.merkle_root;
+        whitelist_tier.merkle_root = merkle_root;
+
+        emit!(MerkleRootUpdated {
+            ido_account: ido_account.key(),
+            tier,
+            old_root,
+            new_root: merkle_root,
+        });
         Ok(())
     }
 
@@ -137,6 +537,56 @@ pub mod solana_launchpad {
             ErrorCode::LowUsdc
         );
         let ido_account = &ctx.accounts.ido_account;
+        require!(
+            !matches!(ido_account.sale_mode, SaleMode::Lbp | SaleMode::SealedBid),
+            ErrorCode::InvalidSaleMode
+        );
+
+        let mut tokens_bid = 0;
+        let mut price = 0;
+        let amount_in = if ido_account.sale_mode == SaleMode::DutchAuction {
+            require!(
+                ido_account.auction_sold < ido_account.num_ido_tokens_public,
+                ErrorCode::PublicSoldOut
+            );
+            price = ido_account.auction_price(Clock::get()?.unix_timestamp);
+            let (amount_in, tokens) = ido_account.auction_bid(amount_in, price);
+            require!(tokens > 0, ErrorCode::BidTooSmall);
+            tokens_bid = tokens;
+            amount_in
+        } else {
+            amount_in
+        };
+
+        let redeemable_supply = ctx
+            .accounts
+            .redeemable_mint
+            .supply
+            .checked_add(amount_in)
+            .unwrap();
+        require!(
+            ido_account.hard_cap_allows(redeemable_supply),
+            ErrorCode::HardCapReached
+        );
+        require!(
+            ido_account.public_allocation_allows(redeemable_supply),
+            ErrorCode::PublicSoldOut
+        );
+
+        require!(
+            amount_in >= ido_account.min_contribution,
+            ErrorCode::BelowMinContribution
+        );
+        let usdc_deposited = ctx
+            .accounts
+            .deposit_ledger
+            .usdc_deposited
+            .checked_add(amount_in)
+            .unwrap();
+        require!(
+            ido_account.max_contribution == 0 || usdc_deposited <= ido_account.max_contribution,
+            ErrorCode::AboveMaxContribution
+        );
 
         msg!("TRANSFER USDC TO POOL");
         // Transfer usdc to pool
@@ -170,21 +620,36 @@ pub mod solana_launchpad {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
         token::mint_to(cpi_ctx, amount_in)?;
 
+        let deposit_ledger = &mut ctx.accounts.deposit_ledger;
+        deposit_ledger.user_authority = ctx.accounts.user_authority.key();
+        deposit_ledger.ido_account = ctx.accounts.ido_account.key();
+        deposit_ledger.usdc_deposited = usdc_deposited;
+
+        if tokens_bid > 0 {
+            deposit_ledger.tokens_bid = deposit_ledger.tokens_bid.checked_add(tokens_bid).unwrap();
+            let ido_account = &mut ctx.accounts.ido_account;
+            ido_account.auction_sold = ido_account.auction_sold.checked_add(tokens_bid).unwrap();
+            if ido_account.auction_sold == ido_account.num_ido_tokens_public {
+                msg!("AUCTION SOLD OUT");
+                ido_account.clearing_price = price;
+            }
+        }
+
         Ok(())
     }
 
-    #[access_control(withdraw_phase(&ctx.accounts.ido_account))]
-    pub fn exchange_redeemable_for_watermelon(
-        ctx: Context<ExchangeRedeemableForWatermelon>,
+    // Redeemable can only be burnt back to USDC while deposits are open, so the
+    // `redeemable_minted` snapshot taken on the first claim stays final.
+    #[access_control(deposit_phase(&ctx.accounts.ido_account))]
+    pub fn exchange_redeemable_for_usdc(
+        ctx: Context<ExchangeRedeemableForUsdc>,
         amount_in: u64,
     ) -> ProgramResult {
-        msg!("EXCHANGE REDEEMABLE FOR WATERMELON");
-        if !ctx.accounts.ido_account.pool_info.is_initialized() {
-            ctx.accounts.ido_account.pool_info.is_initialized = true;
-            ctx.accounts.ido_account.pool_info.redeemable_minted =
-                ctx.accounts.redeemable_mint.supply;
-        }
-
+        msg!("EXCHANGE REDEEMABLE FOR USDC");
+        require!(
+            ctx.accounts.ido_account.sale_mode != SaleMode::DutchAuction,
+            ErrorCode::AuctionBidFinal
+        );
         require!(
             ctx.accounts.user_redeemable.amount >= amount_in,
             ErrorCode::LowRedeemable
@@ -208,63 +673,860 @@ pub mod solana_launchpad {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
         token::burn(cpi_ctx, amount_in)?;
 
-        let amount_out = (amount_in as u128)
-            .checked_mul(ctx.accounts.ido_account.num_ido_tokens_public as u128)
-            .unwrap()
-            .checked_div(ctx.accounts.ido_account.pool_info.redeemable_minted as u128)
-            .unwrap();
-
+        msg!("TRANSFER USDC TO USER");
+        // Transfer USDC from pool back to user
         let cpi_accounts = Transfer {
-            from: ctx.accounts.pool_watermelon.to_account_info(),
-            to: ctx.accounts.user_watermelon.to_account_info(),
+            from: ctx.accounts.pool_usdc.to_account_info(),
+            to: ctx.accounts.user_usdc.to_account_info(),
             authority: ctx.accounts.ido_account.to_account_info(),
         };
-
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount_in)?;
 
-        token::transfer(cpi_ctx, amount_out as u64)?;
-
-        msg!("SEND RENT BACK TO USER IF EMPTY");
-        ctx.accounts.user_redeemable.reload()?;
-        if ctx.accounts.user_redeemable.amount == 0 {
-            let cpi_accounts = CloseAccount {
-                account: ctx.accounts.user_redeemable.to_account_info(),
-                destination: ctx.accounts.user_authority.to_account_info(),
-                authority: ctx.accounts.ido_account.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-            token::close_account(cpi_ctx)?;
-        }
+        // Withdrawn USDC frees up room under the max contribution.
+        let deposit_ledger = &mut ctx.accounts.deposit_ledger;
+        deposit_ledger.usdc_deposited = deposit_ledger.usdc_deposited.saturating_sub(amount_in);
 
         Ok(())
     }
 
-    #[access_control(ido_is_over(&ctx.accounts.ido_account))]
-    pub fn withdraw_pool_usdc(ctx: Context<WithdrawPoolUsdc>) -> ProgramResult {
-        msg!("WITHDRAW POOL USDC");
+    #[access_control(lbp_phase(&ctx.accounts.ido_account))]
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> ProgramResult {
+        msg!("SWAP USDC FOR WATERMELON");
+        // The pricing series only converges fast enough for swaps up to half the
+        // USDC balance, larger buys are split.
+        require!(
+            amount_in <= ctx.accounts.ido_account.lbp_balance_in() / 2,
+            ErrorCode::SwapTooLarge
+        );
+        require!(
+            ctx.accounts.user_usdc.amount >= amount_in,
+            ErrorCode::LowUsdc
+        );
 
-        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let now = Clock::get()?.unix_timestamp;
+        let amount_out = ctx.accounts.ido_account.lbp_amount_out(amount_in, now);
+        require!(amount_out > 0, ErrorCode::InvalidAmountOut);
+        require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+
+        let ido_account = &mut ctx.accounts.ido_account;
+        ido_account.lbp_usdc_in = ido_account.lbp_usdc_in.checked_add(amount_in).unwrap();
+        ido_account.lbp_tokens_out = ido_account.lbp_tokens_out.checked_add(amount_out).unwrap();
+
+        msg!("TRANSFER USDC TO POOL");
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_usdc.to_account_info(),
+            to: ctx.accounts.pool_usdc.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount_in)?;
 
+        msg!("TRANSFER WATERMELON TO USER");
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
         let seeds = &[
             ido_name.trim_ascii_whitespace(),
             &[ctx.accounts.ido_account.bumps.ido_account],
         ];
-
         let signer = &[&seeds[..]];
 
         let cpi_accounts = Transfer {
-            from: ctx.accounts.pool_usdc.to_account_info(),
-            to: ctx.accounts.user_usdc.to_account_info(),
+            from: ctx.accounts.pool_watermelon.to_account_info(),
+            to: ctx.accounts.user_watermelon.to_account_info(),
             authority: ctx.accounts.ido_account.to_account_info(),
         };
-
         let cpi_program = ctx.accounts.token_program.to_account_info();
-
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, ctx.accounts.pool_usdc.amount)?;
+        token::transfer(cpi_ctx, amount_out)?;
 
         Ok(())
     }
+
+    // The commitment hides the bid until the reveal period, the escrow must cover it.
+    #[access_control(deposit_phase(&ctx.accounts.ido_account))]
+    pub fn commit_bid(ctx: Context<CommitBid>, commitment: [u8; 32], escrow: u64) -> ProgramResult {
+        msg!("COMMIT BID");
+        require!(
+            ctx.accounts.ido_account.sale_mode == SaleMode::SealedBid,
+            ErrorCode::InvalidSaleMode
+        );
+        require!(ctx.accounts.user_usdc.amount >= escrow, ErrorCode::LowUsdc);
+
+        let bid = &mut ctx.accounts.bid;
+        bid.user_authority = ctx.accounts.user_authority.key();
+        bid.ido_account = ctx.accounts.ido_account.key();
+        bid.commitment = commitment;
+        bid.escrow = escrow;
+
+        let ido_account = &mut ctx.accounts.ido_account;
+        ido_account.bid_escrow = ido_account.bid_escrow.checked_add(escrow).unwrap();
+        ido_account.bids_open = ido_account.bids_open.checked_add(1).unwrap();
+
+        msg!("TRANSFER USDC TO POOL");
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_usdc.to_account_info(),
+            to: ctx.accounts.pool_usdc.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, escrow)?;
+
+        Ok(())
+    }
+
+    // Bids that are never revealed don't count, their escrow is refunded at settlement.
+    #[access_control(reveal_phase(&ctx.accounts.ido_account))]
+    pub fn reveal_bid(
+        ctx: Context<RevealBid>,
+        price: u64,
+        amount: u64,
+        salt: [u8; 32],
+    ) -> ProgramResult {
+        msg!("REVEAL BID");
+        let bid = &mut ctx.accounts.bid;
+        require!(!bid.revealed, ErrorCode::InvalidReveal);
+        require!(amount > 0, ErrorCode::BidTooSmall);
+        let commitment = keccak::hashv(&[&price.to_le_bytes(), &amount.to_le_bytes(), &salt]).0;
+        require!(commitment == bid.commitment, ErrorCode::InvalidReveal);
+        require!(
+            IdoAccount::bid_cost(amount, price) <= bid.escrow,
+            ErrorCode::BidUnderfunded
+        );
+
+        let ido_account = &mut ctx.accounts.ido_account;
+        let level = ido_account
+            .price_level(price)
+            .ok_or(ErrorCode::InvalidBidPrice)?;
+        ido_account.bid_demand[level] = ido_account.bid_demand[level].checked_add(amount).unwrap();
+
+        bid.price = price;
+        bid.amount = amount;
+        bid.revealed = true;
+        Ok(())
+    }
+
+    // Permissionless crank, anyone can settle the auction once the reveal period is over.
+    #[access_control(reveal_over(&ctx.accounts.ido_account))]
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> ProgramResult {
+        msg!("SETTLE AUCTION");
+        let ido_account = &mut ctx.accounts.ido_account;
+        require!(
+            ido_account.sale_mode == SaleMode::SealedBid,
+            ErrorCode::InvalidSaleMode
+        );
+        require!(!ido_account.auction_settled, ErrorCode::AuctionSettled);
+
+        let (clearing_price, sold) = ido_account.sealed_bid_clearing();
+        ido_account.clearing_price = clearing_price;
+        ido_account.auction_sold = sold;
+        ido_account.auction_settled = true;
+
+        emit!(AuctionSettled {
+            ido_account: ido_account.key(),
+            clearing_price,
+            sold,
+        });
+        Ok(())
+    }
+
+    // Pays out the watermelon a bid won at the clearing price and refunds the rest of
+    // its escrow, then closes the bid.
+    #[access_control(not_paused(&ctx.accounts.ido_account))]
+    pub fn settle_bid(ctx: Context<SettleBid>) -> ProgramResult {
+        msg!("SETTLE BID");
+        // Bids of a cancelled auction are refunded in full.
+        require!(
+            ctx.accounts.ido_account.auction_settled || ctx.accounts.ido_account.cancelled,
+            ErrorCode::AuctionNotSettled
+        );
+
+        let bid = &ctx.accounts.bid;
+        let ido_account = &mut ctx.accounts.ido_account;
+        let amount_out = if ido_account.auction_settled {
+            ido_account.bid_fill(bid)?
+        } else {
+            0
+        };
+        let refund = bid
+            .escrow
+            .checked_sub(ido_account.auction_cost(amount_out, true))
+            .ok_or(ErrorCode::MathOverflow)?;
+        ido_account.auction_claimed = ido_account.auction_claimed.checked_add(amount_out).unwrap();
+        ido_account.bid_escrow = ido_account
+            .bid_escrow
+            .checked_sub(bid.escrow)
+            .ok_or(ErrorCode::MathOverflow)?;
+        ido_account.bids_open = ido_account
+            .bids_open
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        if amount_out > 0 {
+            msg!("TRANSFER WATERMELON TO USER");
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_watermelon.to_account_info(),
+                to: ctx.accounts.user_watermelon.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, amount_out)?;
+        }
+
+        if refund > 0 {
+            msg!("REFUND ESCROWED USDC");
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_usdc.to_account_info(),
+                to: ctx.accounts.user_usdc.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, refund)?;
+        }
+
+        Ok(())
+    }
+
+    #[access_control(withdraw_phase(&ctx.accounts.ido_account))]
+    pub fn exchange_redeemable_for_watermelon(
+        ctx: Context<ExchangeRedeemableForWatermelon>,
+        amount_in: u64,
+    ) -> ProgramResult {
+        msg!("EXCHANGE REDEEMABLE FOR WATERMELON");
+        ctx.accounts
+            .ido_account
+            .snapshot_redeemable(ctx.accounts.redeemable_mint.supply);
+        require!(
+            ctx.accounts.ido_account.soft_cap_reached(),
+            ErrorCode::SoftCapNotReached
+        );
+
+        require!(
+            ctx.accounts.user_redeemable.amount >= amount_in,
+            ErrorCode::LowRedeemable
+        );
+
+        let (amount_out, refund) = ctx
+            .accounts
+            .ido_account
+            .claim_amounts(amount_in, &ctx.accounts.deposit_ledger);
+        if ctx.accounts.ido_account.sale_mode == SaleMode::DutchAuction {
+            let ido_account = &mut ctx.accounts.ido_account;
+            ido_account.auction_claimed =
+                ido_account.auction_claimed.checked_add(amount_out).unwrap();
+            let deposit_ledger = &mut ctx.accounts.deposit_ledger;
+            // Redeemable can be transferred, only what this wallet bid is claimable.
+            deposit_ledger.tokens_bid = deposit_ledger
+                .tokens_bid
+                .checked_sub(amount_out)
+                .ok_or(ErrorCode::LowRedeemable)?;
+            deposit_ledger.usdc_deposited = deposit_ledger
+                .usdc_deposited
+                .checked_sub(amount_in)
+                .ok_or(ErrorCode::LowRedeemable)?;
+        }
+        if ctx.accounts.ido_account.public_vesting.is_enabled() {
+            let ido_account = &mut ctx.accounts.ido_account;
+            ido_account.vesting_locked =
+                ido_account.vesting_locked.checked_add(amount_out).unwrap();
+        }
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        msg!("BURN REDEEMABLE");
+        // Burn Redeemable
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.redeemable_mint.to_account_info(),
+            to: ctx.accounts.user_redeemable.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::burn(cpi_ctx, amount_in)?;
+
+        if ctx.accounts.ido_account.public_vesting.is_enabled() {
+            msg!("LOCK WATERMELON IN VESTING");
+            let mut vesting = Account::<Vesting>::try_from(&ctx.accounts.vesting)?;
+            vesting.public_total = vesting.public_total.checked_add(amount_out).unwrap();
+            vesting.exit(ctx.program_id)?;
+        } else {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_watermelon.to_account_info(),
+                to: ctx.accounts.user_watermelon.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+
+            token::transfer(cpi_ctx, amount_out)?;
+        }
+
+        if refund > 0 {
+            msg!("REFUND UNFILLED USDC");
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_usdc.to_account_info(),
+                to: ctx.accounts.user_usdc.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, refund)?;
+        }
+
+        msg!("SEND RENT BACK TO USER IF EMPTY");
+        ctx.accounts.user_redeemable.reload()?;
+        if ctx.accounts.user_redeemable.amount == 0 {
+            let cpi_accounts = CloseAccount {
+                account: ctx.accounts.user_redeemable.to_account_info(),
+                destination: ctx.accounts.user_authority.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::close_account(cpi_ctx)?;
+        }
+
+        Ok(())
+    }
+
+    // Releases whatever has vested on both schedules since the last claim.
+    #[access_control(not_paused(&ctx.accounts.ido_account))]
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> ProgramResult {
+        msg!("CLAIM VESTED WATERMELON");
+        let ido_account = &mut ctx.accounts.ido_account;
+        let vesting = &mut ctx.accounts.vesting;
+        let start = ido_account.ido_times.end_deposits;
+        let now = Clock::get()?.unix_timestamp;
+        let private_unlocked =
+            ido_account
+                .private_vesting
+                .unlocked(vesting.private_total, start, now);
+        let public_unlocked = ido_account
+            .public_vesting
+            .unlocked(vesting.public_total, start, now);
+        let private_amount = private_unlocked
+            .checked_sub(vesting.private_released)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let public_amount = public_unlocked
+            .checked_sub(vesting.public_released)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let amount = private_amount.checked_add(public_amount).unwrap();
+        require!(amount > 0, ErrorCode::NothingVested);
+        vesting.private_released = private_unlocked;
+        vesting.public_released = public_unlocked;
+        ido_account.vesting_locked = ido_account
+            .vesting_locked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let ido_name = ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_watermelon.to_account_info(),
+            to: ctx.accounts.user_watermelon.to_account_info(),
+            authority: ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        Ok(())
+    }
+
+    #[access_control(refund_phase(&ctx.accounts.ido_account))]
+    pub fn refund_redeemable(ctx: Context<RefundRedeemable>, amount_in: u64) -> ProgramResult {
+        msg!("REFUND REDEEMABLE");
+        ctx.accounts
+            .ido_account
+            .snapshot_redeemable(ctx.accounts.redeemable_mint.supply);
+        require!(
+            ctx.accounts.ido_account.cancelled || !ctx.accounts.ido_account.soft_cap_reached(),
+            ErrorCode::SoftCapReached
+        );
+
+        require!(
+            ctx.accounts.user_redeemable.amount >= amount_in,
+            ErrorCode::LowRedeemable
+        );
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        msg!("BURN REDEEMABLE");
+        // Burn Redeemable
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.redeemable_mint.to_account_info(),
+            to: ctx.accounts.user_redeemable.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::burn(cpi_ctx, amount_in)?;
+
+        msg!("TRANSFER USDC TO USER");
+        // Redeemable was minted one-to-one, so refund the same amount of USDC
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_usdc.to_account_info(),
+            to: ctx.accounts.user_usdc.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount_in)?;
+
+        msg!("SEND RENT BACK TO USER IF EMPTY");
+        ctx.accounts.user_redeemable.reload()?;
+        if ctx.accounts.user_redeemable.amount == 0 {
+            let cpi_accounts = CloseAccount {
+                account: ctx.accounts.user_redeemable.to_account_info(),
+                destination: ctx.accounts.user_authority.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::close_account(cpi_ctx)?;
+        }
+
+        Ok(())
+    }
+
+    // A whitelisted buyer of a cancelled ido returns the watermelon they hold
+    // from a tier and gets back the USDC recorded in its ledger. The tier's share
+    // of the watermelon still locked in vesting is forfeited back to the pool.
+    #[access_control(ido_cancelled(&ctx.accounts.ido_account))]
+    pub fn refund_private_purchase(
+        ctx: Context<RefundPrivatePurchase>,
+        _tier: u8,
+    ) -> ProgramResult {
+        msg!("REFUND PRIVATE PURCHASE");
+        let usdc_paid = ctx.accounts.whitelist_ledger.usdc_paid;
+        require!(usdc_paid > 0, ErrorCode::NothingToRefund);
+
+        let received = ctx.accounts.whitelist_ledger.watermelon_received;
+        let mut locked = 0;
+        if ctx.accounts.ido_account.private_vesting.is_enabled() {
+            // Every tier's purchase vests at the same pace, so the tier's share of
+            // what is still locked is in proportion to what it bought.
+            let mut vesting = Account::<Vesting>::try_from(&ctx.accounts.vesting)?;
+            let locked_total = vesting
+                .private_total
+                .checked_sub(vesting.private_released)
+                .ok_or(ErrorCode::MathOverflow)?;
+            locked = (locked_total as u128)
+                .checked_mul(received as u128)
+                .and_then(|locked| locked.checked_div(vesting.private_total as u128))
+                .ok_or(ErrorCode::MathOverflow)? as u64;
+            let released = received
+                .checked_sub(locked)
+                .ok_or(ErrorCode::MathOverflow)?;
+            vesting.private_total = vesting
+                .private_total
+                .checked_sub(received)
+                .ok_or(ErrorCode::MathOverflow)?;
+            vesting.private_released = vesting
+                .private_released
+                .checked_sub(released)
+                .ok_or(ErrorCode::MathOverflow)?;
+            vesting.exit(ctx.program_id)?;
+        }
+        let returned = received
+            .checked_sub(locked)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if returned > 0 {
+            msg!("RETURN WATERMELON TO POOL");
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.user_watermelon.to_account_info(),
+                to: ctx.accounts.pool_watermelon.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token::transfer(cpi_ctx, returned)?;
+        }
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        msg!("TRANSFER USDC TO USER");
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_usdc.to_account_info(),
+            to: ctx.accounts.user_usdc.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, usdc_paid)?;
+
+        let whitelist_ledger = &mut ctx.accounts.whitelist_ledger;
+        let ido_account = &mut ctx.accounts.ido_account;
+        ido_account.vesting_locked = ido_account
+            .vesting_locked
+            .checked_sub(locked)
+            .ok_or(ErrorCode::MathOverflow)?;
+        ido_account.private_raised = ido_account
+            .private_raised
+            .checked_sub(usdc_paid)
+            .ok_or(ErrorCode::MathOverflow)?;
+        ido_account.private_sold = ido_account
+            .private_sold
+            .checked_sub(received)
+            .ok_or(ErrorCode::MathOverflow)?;
+        whitelist_ledger.usdc_paid = 0;
+        whitelist_ledger.watermelon_received = 0;
+        Ok(())
+    }
+
+    #[access_control(ido_is_over(&ctx.accounts.ido_account))]
+    pub fn withdraw_pool_usdc(ctx: Context<WithdrawPoolUsdc>) -> ProgramResult {
+        msg!("WITHDRAW POOL USDC");
+        ctx.accounts
+            .ido_account
+            .snapshot_redeemable(ctx.accounts.redeemable_mint.supply);
+
+        // USDC still owed to redeemable holders stays in the pool for their
+        // refunds, e.g. everything when the soft cap was missed.
+        let reserved = ctx
+            .accounts
+            .ido_account
+            .reserved_usdc(ctx.accounts.redeemable_mint.supply)?;
+        let amount = ctx.accounts.pool_usdc.amount.checked_sub(reserved).unwrap();
+        let fee = ctx.accounts.ido_account.protocol_fee(amount);
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+
+        let signer = &[&seeds[..]];
+
+        if fee > 0 {
+            msg!("PAY PROTOCOL FEE");
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.pool_usdc.to_account_info(),
+                to: ctx.accounts.treasury_usdc.to_account_info(),
+                authority: ctx.accounts.ido_account.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, fee)?;
+        }
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_usdc.to_account_info(),
+            to: ctx.accounts.user_usdc.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount - fee)?;
+
+        Ok(())
+    }
+
+    #[access_control(ido_is_over(&ctx.accounts.ido_account))]
+    pub fn withdraw_unsold_watermelon(ctx: Context<WithdrawUnsoldWatermelon>) -> ProgramResult {
+        msg!("WITHDRAW UNSOLD WATERMELON");
+        require!(!ctx.accounts.ido_account.cancelled, ErrorCode::IdoCancelled);
+        ctx.accounts
+            .ido_account
+            .snapshot_redeemable(ctx.accounts.redeemable_mint.supply);
+
+        let unsold = ctx.accounts.ido_account.unsold()?;
+        let amount = unsold
+            .checked_sub(ctx.accounts.ido_account.unsold_withdrawn)
+            .unwrap();
+        require!(amount > 0, ErrorCode::NothingToWithdraw);
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_watermelon.to_account_info(),
+            to: ctx.accounts.user_watermelon.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        ctx.accounts.ido_account.unsold_withdrawn = unsold;
+        Ok(())
+    }
+
+    // Sends the watermelon of a cancelled ido back to the authority, keeping what
+    // buyers are still owed. Can be called again as whitelisted buyers return theirs.
+    #[access_control(ido_cancelled(&ctx.accounts.ido_account))]
+    pub fn withdraw_cancelled_watermelon(ctx: Context<WithdrawUnsoldWatermelon>) -> ProgramResult {
+        msg!("WITHDRAW CANCELLED WATERMELON");
+        let amount = ctx
+            .accounts
+            .pool_watermelon
+            .amount
+            .checked_sub(ctx.accounts.ido_account.owed_watermelon()?)
+            .unwrap();
+        require!(amount > 0, ErrorCode::NothingToWithdraw);
+
+        let ido_name = ctx.accounts.ido_account.ido_name.as_ref();
+        let seeds = &[
+            ido_name.trim_ascii_whitespace(),
+            &[ctx.accounts.ido_account.bumps.ido_account],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_watermelon.to_account_info(),
+            to: ctx.accounts.user_watermelon.to_account_info(),
+            authority: ctx.accounts.ido_account.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+        Ok(())
+    }
+
+    pub fn initialize_stake_pool(
+        ctx: Context<InitializeStakePool>,
+        bumps: StakePoolBumps,
+        lock_period: i64,
+    ) -> ProgramResult {
+        msg!("INITIALIZE STAKE POOL");
+        require!(lock_period >= 0, ErrorCode::InvalidStake);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.stake_mint = ctx.accounts.stake_mint.key();
+        stake_pool.stake_vault = ctx.accounts.stake_vault.key();
+        stake_pool.lock_period = lock_period;
+        stake_pool.bumps = bumps;
+        Ok(())
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> ProgramResult {
+        msg!("STAKE");
+        require!(amount > 0, ErrorCode::InvalidStake);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_stake.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        // Topping up restarts the lock, without cutting short one held by a snapshot.
+        let now = Clock::get()?.unix_timestamp;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        stake_account.user_authority = ctx.accounts.user_authority.key();
+        stake_account.amount = stake_account.amount.checked_add(amount).unwrap();
+        stake_account.locked_until = stake_account
+            .locked_until
+            .max(now.checked_add(stake_pool.lock_period).unwrap());
+        stake_pool.total_staked = stake_pool.total_staked.checked_add(amount).unwrap();
+        Ok(())
+    }
+
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> ProgramResult {
+        msg!("UNSTAKE");
+        let now = Clock::get()?.unix_timestamp;
+        let stake_account = &mut ctx.accounts.stake_account;
+        require!(now >= stake_account.locked_until, ErrorCode::StakeLocked);
+        require!(
+            amount > 0 && amount <= stake_account.amount,
+            ErrorCode::InsufficientStake
+        );
+        stake_account.amount -= amount;
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.total_staked = stake_pool.total_staked.checked_sub(amount).unwrap();
+
+        let seeds = &[b"stake_pool".as_ref(), &[stake_pool.bumps.stake_pool]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            to: ctx.accounts.user_stake.to_account_info(),
+            authority: stake_pool.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+        Ok(())
+    }
+
+    // Registers the caller's current stake for the ido. Calling it again replaces
+    // the previous snapshot.
+    #[access_control(stake_registration_phase(&ctx.accounts.ido_account))]
+    pub fn snapshot_stake(ctx: Context<SnapshotStake>) -> ProgramResult {
+        msg!("SNAPSHOT STAKE");
+        let stake_account = &mut ctx.accounts.stake_account;
+        let stake_snapshot = &mut ctx.accounts.stake_snapshot;
+        let ido_account = &mut ctx.accounts.ido_account;
+
+        ido_account.total_stake_snapshot = ido_account
+            .total_stake_snapshot
+            .checked_sub(stake_snapshot.amount)
+            .and_then(|total| total.checked_add(stake_account.amount))
+            .ok_or(ErrorCode::MathOverflow)?;
+        stake_snapshot.user_authority = ctx.accounts.user_authority.key();
+        stake_snapshot.ido_account = ido_account.key();
+        stake_snapshot.amount = stake_account.amount;
+
+        // The snapshot is only spent in the private round, the stake can't move
+        // to another wallet before then.
+        stake_account.locked_until = stake_account
+            .locked_until
+            .max(ido_account.ido_times.end_whitelisted);
+        Ok(())
+    }
+}
+
+fn set_paused(accounts: &mut PausePool, paused: bool) -> ProgramResult {
+    accounts.ido_account.paused = paused;
+
+    emit!(PoolPaused {
+        ido_account: accounts.ido_account.key(),
+        authority: accounts.authority.key(),
+        paused,
+    });
+    Ok(())
+}
+
+// Buys `amount_out` watermelon from a whitelist tier, up to the `max_amount` the
+// wallet is allowed.
+fn buy_private(
+    accounts: &mut ExchangeUsdcForWaterMelon,
+    tier: u8,
+    max_amount: u64,
+    amount_out: u64,
+) -> ProgramResult {
+    let ido_account = &accounts.ido_account;
+    let whitelist_tier = ido_account.tiers[tier as usize];
+
+    let watermelon_received = accounts
+        .whitelist_ledger
+        .watermelon_received
+        .checked_add(amount_out)
+        .unwrap();
+    require!(
+        watermelon_received <= max_amount,
+        ErrorCode::AllocationExceeded
+    );
+
+    // Never dip into the tokens backing the public redeemables.
+    let private_sold = ido_account.private_sold.checked_add(amount_out).unwrap();
+    require!(
+        private_sold <= ido_account.num_ido_tokens_private,
+        ErrorCode::PrivateSoldOut
+    );
+    let tier_sold = whitelist_tier.sold.checked_add(amount_out).unwrap();
+    require!(
+        tier_sold <= whitelist_tier.num_ido_tokens,
+        ErrorCode::TierSoldOut
+    );
+
+    let amount_paid = (amount_out as u128)
+        .checked_mul(whitelist_tier.target_investment as u128)
+        .unwrap()
+        .checked_div(whitelist_tier.num_ido_tokens as u128)
+        .unwrap();
+
+    require!(amount_out > 0, ErrorCode::InvalidAmountOut);
+    require!(amount_paid > 0, ErrorCode::InvalidAmountPaid);
+
+    // Transfer user's USDC to pool USDC account
+    let cpi_accounts = Transfer {
+        from: accounts.user_usdc.to_account_info(),
+        to: accounts.pool_usdc.to_account_info(),
+        authority: accounts.user_authority.to_account_info(),
+    };
+    let cpi_program = accounts.token_program.to_account_info();
+
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    token::transfer(cpi_ctx, amount_paid as u64)?;
+
+    let ido_name = ido_account.ido_name.as_ref();
+    let seeds = &[
+        ido_name.trim_ascii_whitespace(),
+        &[ido_account.bumps.ido_account],
+    ];
+    let signer = &[&seeds[..]];
+    if ido_account.private_vesting.is_enabled() {
+        msg!("LOCK WATERMELON IN VESTING");
+        let mut vesting = Account::<Vesting>::try_from(&accounts.vesting)?;
+        vesting.private_total = vesting.private_total.checked_add(amount_out).unwrap();
+        vesting.exit(&crate::ID)?;
+    } else {
+        // Mint Watermelon to user
+        let cpi_accounts = Transfer {
+            from: accounts.pool_watermelon.to_account_info(),
+            to: accounts.user_watermelon.to_account_info(),
+            authority: ido_account.to_account_info(),
+        };
+        let cpi_program = accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount_out)?;
+    }
+
+    let ido_account = &mut accounts.ido_account;
+    if ido_account.private_vesting.is_enabled() {
+        ido_account.vesting_locked = ido_account.vesting_locked.checked_add(amount_out).unwrap();
+    }
+    ido_account.tiers[tier as usize].sold = tier_sold;
+    ido_account.private_sold = private_sold;
+    ido_account.private_raised = ido_account
+        .private_raised
+        .checked_add(amount_paid as u64)
+        .unwrap();
+
+    msg!("RECORD PURCHASE IN LEDGER");
+    let whitelist_ledger = &mut accounts.whitelist_ledger;
+    whitelist_ledger.user_authority = accounts.user_authority.key();
+    whitelist_ledger.ido_account = accounts.ido_account.key();
+    whitelist_ledger.tier = tier;
+    whitelist_ledger.max_amount = max_amount;
+    whitelist_ledger.usdc_paid = whitelist_ledger
+        .usdc_paid
+        .checked_add(amount_paid as u64)
+        .unwrap();
+    whitelist_ledger.watermelon_received = watermelon_received;
+    Ok(())
 }
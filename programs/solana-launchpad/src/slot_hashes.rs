@@ -0,0 +1,35 @@
+// Layout of the SlotHashes sysvar: an entry count, then (slot, hash) entries
+// from the newest slot down. Read in place, deserializing all of it costs too
+// much compute.
+const LEN_SIZE: usize = 8;
+const SLOT_SIZE: usize = 8;
+const HASH_SIZE: usize = 32;
+const ENTRY_SIZE: usize = SLOT_SIZE + HASH_SIZE;
+
+pub struct SlotHashes {}
+
+impl SlotHashes {
+  // Only the last 512 slots are kept, older ones are not found.
+  pub fn find(data: &[u8], slot: u64) -> Option<[u8; 32]> {
+    let mut len = [0u8; LEN_SIZE];
+    len.copy_from_slice(data.get(..LEN_SIZE)?);
+    let len = u64::from_le_bytes(len) as usize;
+
+    for i in 0..len {
+      let at = LEN_SIZE + i * ENTRY_SIZE;
+      let entry = data.get(at..at + ENTRY_SIZE)?;
+      let mut entry_slot = [0u8; SLOT_SIZE];
+      entry_slot.copy_from_slice(&entry[..SLOT_SIZE]);
+      let entry_slot = u64::from_le_bytes(entry_slot);
+      if entry_slot == slot {
+        let mut hash = [0u8; HASH_SIZE];
+        hash.copy_from_slice(&entry[SLOT_SIZE..]);
+        return Some(hash);
+      }
+      if entry_slot < slot {
+        return None;
+      }
+    }
+    None
+  }
+}
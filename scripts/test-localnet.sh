@@ -0,0 +1,28 @@
+#!/usr/bin/env bash
+# `anchor test` loads the program at genesis, without the upgrade authority
+# `init_launchpad` checks for. Unless the program on the cluster was deployed with
+# `anchor deploy`, deploy it to a validator of our own and run the tests there.
+set -euo pipefail
+
+PROGRAM_KEYPAIR=target/deploy/solana_launchpad-keypair.json
+PROGRAM_SO=target/deploy/solana_launchpad.so
+PROGRAM_ID=$(solana address -k "$PROGRAM_KEYPAIR")
+URL=${ANCHOR_PROVIDER_URL:-http://localhost:8899}
+
+if ! solana program show "$PROGRAM_ID" --url "$URL" 2>/dev/null | grep -q "ProgramData Address"; then
+  URL=http://localhost:8999
+  solana-test-validator --reset --quiet \
+    --ledger .anchor/deploy-ledger \
+    --rpc-port 8999 --faucet-port 9910 \
+    --mint "$(solana address -k "$ANCHOR_WALLET")" &
+  VALIDATOR=$!
+  trap 'kill $VALIDATOR' EXIT
+  until solana cluster-version --url "$URL" >/dev/null 2>&1; do sleep 1; done
+
+  solana program deploy "$PROGRAM_SO" \
+    --program-id "$PROGRAM_KEYPAIR" \
+    --keypair "$ANCHOR_WALLET" \
+    --url "$URL"
+fi
+
+ANCHOR_PROVIDER_URL=$URL yarn run ts-mocha -p ./tsconfig.json -t 1000000 "tests/**/*.ts"
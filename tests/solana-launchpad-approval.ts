@@ -0,0 +1,193 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  findRelatedProgramAddress,
+  requestAirdrop,
+  createIdo,
+  createTokenAccount,
+  getTokenAccount,
+} from "./utils";
+
+describe("launchpad ido approval", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should create the ido pending review", async () => {
+    let idoAccount: anchor.web3.PublicKey;
+    ({ launchpadConfig, idoTimes, idoName, idoAccount } = await createIdo(
+      program,
+      {
+        times: { startIdo: 5, endWhitelisted: 6, endDeposits: 20, endIdo: 21 },
+        pending: true,
+      }
+    ));
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.deepEqual(idoAccountInfo.status, { pending: {} });
+  });
+
+  const investor = anchor.web3.Keypair.generate();
+  async function initUserRedeemable() {
+    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [userRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        investor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    await program.rpc.initUserRedeemable({
+      accounts: {
+        userAuthority: investor.publicKey,
+        userRedeemable,
+        idoAccount,
+        redeemableMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+      signers: [investor],
+    });
+  }
+
+  it("should stay closed until the admin approves it", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+    await requestAirdrop(investor.publicKey, provider);
+
+    await assert.rejects(
+      initUserRedeemable(),
+      (err: any) => err.msg === "IDO is not approved by the launchpad"
+    );
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    // Only the launchpad admin reviews idos.
+    await assert.rejects(
+      program.rpc.reviewIdo(true, {
+        accounts: {
+          admin: investor.publicKey,
+          launchpadConfig,
+          idoAccount,
+        },
+        signers: [investor],
+      }),
+      (err: any) => err.msg === "Unauthorized"
+    );
+
+    const accounts = {
+      admin: provider.wallet.publicKey,
+      launchpadConfig,
+      idoAccount,
+    };
+    await program.rpc.reviewIdo(true, { accounts });
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.deepEqual(idoAccountInfo.status, { approved: {} });
+
+    await initUserRedeemable();
+
+    await assert.rejects(
+      program.rpc.reviewIdo(false, { accounts }),
+      (err: any) => err.msg === "IDO has already been reviewed"
+    );
+  });
+
+  it("should close a rejected ido and free its name", async () => {
+    const {
+      idoName: rejectedName,
+      idoAccount,
+      registryEntry,
+      registryIndex,
+      usdcMint,
+      poolUsdc,
+      poolWatermelon,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, { pending: true });
+    const idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+    const accounts = {
+      admin: provider.wallet.publicKey,
+      launchpadConfig,
+      idoAuthority: provider.wallet.publicKey,
+      idoAuthorityWatermelon,
+      idoAuthorityUsdc,
+      idoAccount,
+      registryEntry,
+      poolWatermelon,
+      poolUsdc,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await assert.rejects(
+      program.rpc.closeRejectedIdo({ accounts }),
+      (err: any) => err.msg === "IDO has not been rejected"
+    );
+
+    await program.rpc.reviewIdo(false, {
+      accounts: {
+        admin: provider.wallet.publicKey,
+        launchpadConfig,
+        idoAccount,
+      },
+    });
+
+    // Only the launchpad admin closes idos.
+    await assert.rejects(
+      program.rpc.closeRejectedIdo({
+        accounts: { ...accounts, admin: investor.publicKey },
+        signers: [investor],
+      }),
+      (err: any) => err.msg === "Unauthorized"
+    );
+
+    await program.rpc.closeRejectedIdo({ accounts });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(idoAuthorityWatermelonInfo.amount.eq(new anchor.BN(5_000_000)));
+    assert.equal(await provider.connection.getAccountInfo(idoAccount), null);
+    assert.equal(await provider.connection.getAccountInfo(poolWatermelon), null);
+    assert.equal(await provider.connection.getAccountInfo(poolUsdc), null);
+
+    // The registry keeps the entry, marked as closed.
+    const registryEntryInfo = await program.account.idoRegistryEntry.fetch(
+      registryEntry
+    );
+    assert.ok(registryEntryInfo.closed);
+
+    // The name can be registered again, under a new registry index.
+    const { idoAccount: newIdoAccount, registryIndex: newRegistryIndex } =
+      await createIdo(program, {
+        idoName: rejectedName,
+      });
+    assert.ok(newIdoAccount.equals(idoAccount));
+    assert.ok(newRegistryIndex.gt(registryIndex));
+  });
+});
@@ -0,0 +1,368 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+import { MerkleTree } from "merkletreejs";
+import keccak256 from "keccak256";
+
+import {
+  sleep,
+  whitelistLeaf,
+  getProof,
+  getTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  findWhitelistLedgerAddress,
+  findVestingAddress,
+  createATA,
+  createIdo,
+} from "./utils";
+
+describe("launchpad cancel ido", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  const tierIdoAmount = new anchor.BN(2_000_000);
+  const tierTargetInvestment = new anchor.BN(2_000_000);
+  const whitelistMaxAmount = new anchor.BN(1_000_000);
+  const deposit = new anchor.BN(3_000_000);
+  const MERKLE_TIER = 0;
+
+  const buyer = anchor.web3.Keypair.generate();
+  const depositor = anchor.web3.Keypair.generate();
+
+  const tree = new MerkleTree(
+    [buyer, anchor.web3.Keypair.generate()].map((acc) =>
+      whitelistLeaf(acc.publicKey, whitelistMaxAmount)
+    ),
+    keccak256,
+    { sortPairs: true }
+  );
+  const root = Buffer.from(tree.getHexRoot().slice(2), "hex");
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityWatermelon: anchor.web3.PublicKey;
+  let buyerUsdc: anchor.web3.PublicKey;
+  let buyerWatermelon: anchor.web3.PublicKey;
+  let depositorUsdc: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize pool with a whitelist tier", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      times: { startIdo: 5, endWhitelisted: 12, endDeposits: 40, endIdo: 41 },
+      tiers: [
+        {
+          merkleRoot: [...root],
+          targetInvestment: tierTargetInvestment,
+          numIdoTokens: tierIdoAmount,
+          allocation: { merkle: {} },
+        },
+      ],
+    }));
+
+    buyerUsdc = await createATA(buyer, usdcMint, provider, true);
+    buyerWatermelon = await createATA(buyer, watermelonMint, provider);
+    await usdcMintAccount.mintTo(
+      buyerUsdc,
+      provider.wallet.publicKey,
+      [],
+      whitelistMaxAmount.toNumber()
+    );
+
+    depositorUsdc = await createATA(depositor, usdcMint, provider, true);
+    await usdcMintAccount.mintTo(
+      depositorUsdc,
+      provider.wallet.publicKey,
+      [],
+      deposit.toNumber()
+    );
+  });
+
+  it("should buy from the whitelist tier", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      buyer.publicKey,
+      MERKLE_TIER,
+      program.programId
+    );
+    const [vesting] = await findVestingAddress(
+      idoName,
+      buyer.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForWatermelon(
+      MERKLE_TIER,
+      getProof(tree, buyer.publicKey, whitelistMaxAmount),
+      whitelistMaxAmount,
+      whitelistMaxAmount,
+      {
+        accounts: {
+          userAuthority: buyer.publicKey,
+          idoAccount,
+          userUsdc: buyerUsdc,
+          userWatermelon: buyerWatermelon,
+          usdcMint,
+          watermelonMint,
+          poolUsdc,
+          poolWatermelon,
+          whitelistLedger,
+          vesting,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [buyer],
+      }
+    );
+
+    const buyerWatermelonInfo = await getTokenAccount(
+      provider,
+      buyerWatermelon
+    );
+    assert.ok(buyerWatermelonInfo.amount.eq(whitelistMaxAmount));
+  });
+
+  async function depositUsdc() {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+    const [userRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        depositor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      depositor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForRedeemable(deposit, {
+      accounts: {
+        userAuthority: depositor.publicKey,
+        idoAccount,
+        userUsdc: depositorUsdc,
+        userRedeemable,
+        usdcMint,
+        redeemableMint,
+        poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [depositor],
+      preInstructions: [
+        program.instruction.initUserRedeemable({
+          accounts: {
+            userAuthority: depositor.publicKey,
+            userRedeemable,
+            idoAccount,
+            redeemableMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+        }),
+      ],
+    });
+  }
+
+  it("should deposit USDC for redeemable", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    await depositUsdc();
+
+    const depositorUsdcInfo = await getTokenAccount(provider, depositorUsdc);
+    assert.ok(depositorUsdcInfo.amount.eq(new anchor.BN(0)));
+  });
+
+  it("should cancel the ido and close the sale", async () => {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const accounts = {
+      authority: provider.wallet.publicKey,
+      launchpadConfig,
+      idoAccount,
+    };
+
+    // Only the ido authority or the launchpad admin can cancel.
+    await assert.rejects(
+      program.rpc.cancelIdo({
+        accounts: { ...accounts, authority: depositor.publicKey },
+        signers: [depositor],
+      }),
+      (err: any) => err.msg === "Unauthorized"
+    );
+
+    await program.rpc.cancelIdo({ accounts });
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.cancelled);
+
+    await assert.rejects(
+      program.rpc.cancelIdo({ accounts }),
+      (err: any) => err.msg === "IDO is cancelled"
+    );
+    await assert.rejects(
+      depositUsdc(),
+      (err: any) => err.msg === "IDO is cancelled"
+    );
+  });
+
+  it("should refund redeemable for the deposited USDC", async () => {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+    const [userRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        depositor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    await program.rpc.refundRedeemable(deposit, {
+      accounts: {
+        userAuthority: depositor.publicKey,
+        idoAccount,
+        userUsdc: depositorUsdc,
+        userRedeemable,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [depositor],
+    });
+
+    const depositorUsdcInfo = await getTokenAccount(provider, depositorUsdc);
+    assert.ok(depositorUsdcInfo.amount.eq(deposit));
+  });
+
+  it("should refund the whitelisted purchase", async () => {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      buyer.publicKey,
+      MERKLE_TIER,
+      program.programId
+    );
+    const [vesting] = await findVestingAddress(
+      idoName,
+      buyer.publicKey,
+      program.programId
+    );
+    const accounts = {
+      userAuthority: buyer.publicKey,
+      idoAccount,
+      userUsdc: buyerUsdc,
+      userWatermelon: buyerWatermelon,
+      poolUsdc,
+      poolWatermelon,
+      whitelistLedger,
+      vesting,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await program.rpc.refundPrivatePurchase(MERKLE_TIER, {
+      accounts,
+      signers: [buyer],
+    });
+
+    const buyerUsdcInfo = await getTokenAccount(provider, buyerUsdc);
+    assert.ok(buyerUsdcInfo.amount.eq(whitelistMaxAmount));
+    const buyerWatermelonInfo = await getTokenAccount(
+      provider,
+      buyerWatermelon
+    );
+    assert.ok(buyerWatermelonInfo.amount.eq(new anchor.BN(0)));
+
+    await assert.rejects(
+      program.rpc.refundPrivatePurchase(MERKLE_TIER, {
+        accounts,
+        signers: [buyer],
+      }),
+      (err: any) => err.msg === "Nothing to refund"
+    );
+  });
+
+  it("should return the pool watermelon to the authority", async () => {
+    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.withdrawCancelledWatermelon({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userWatermelon: idoAuthorityWatermelon,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(
+      idoAuthorityWatermelonInfo.amount.eq(
+        watermelonIdoPublicAmount.add(tierIdoAmount)
+      )
+    );
+  });
+});
@@ -0,0 +1,308 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  createTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  createATA,
+  findVestingAddress,
+  createIdo,
+} from "./utils";
+
+describe("launchpad dutch auction", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  // Prices are USDC per PRICE_SCALE watermelon. Bids won't sell the allocation
+  // out, so the auction clears at the floor price.
+  const PRICE_SCALE = new anchor.BN(1_000_000);
+  const watermelonIdoPublicAmount = new anchor.BN(100_000_000);
+  const startPrice = new anchor.BN(4_000_000);
+  const floorPrice = new anchor.BN(1_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize dutch auction pool", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: {
+        saleMode: { dutchAuction: {} },
+        auction: { startPrice, floorPrice, stepDuration: new anchor.BN(0) },
+      },
+      times: { startIdo: 5, endWhitelisted: 6, endDeposits: 25, endIdo: 26 },
+    }));
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+  });
+
+  const investors = [
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      deposit: new anchor.BN(2_000_000),
+      usdc: undefined as anchor.web3.PublicKey,
+      redeemable: undefined as anchor.web3.PublicKey,
+    },
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      deposit: new anchor.BN(2_000_000),
+      usdc: undefined as anchor.web3.PublicKey,
+      redeemable: undefined as anchor.web3.PublicKey,
+    },
+  ];
+
+  it("should bid as the price decays", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    for (const investor of investors) {
+      [investor.redeemable] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          investor.keypair.publicKey.toBuffer(),
+          Buffer.from(idoName),
+          Buffer.from("user_redeemable"),
+        ],
+        program.programId
+      );
+
+      investor.usdc = await createATA(
+        investor.keypair,
+        usdcMint,
+        provider,
+        true
+      );
+      await usdcMintAccount.mintTo(
+        investor.usdc,
+        provider.wallet.publicKey,
+        [],
+        investor.deposit.toNumber()
+      );
+
+      const [depositLedger] = await findDepositLedgerAddress(
+        idoName,
+        investor.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.exchangeUsdcForRedeemable(investor.deposit, {
+        accounts: {
+          userAuthority: investor.keypair.publicKey,
+          idoAccount,
+          userUsdc: investor.usdc,
+          userRedeemable: investor.redeemable,
+          usdcMint,
+          redeemableMint,
+          poolUsdc,
+          depositLedger,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [investor.keypair],
+        preInstructions: [
+          program.instruction.initUserRedeemable({
+            accounts: {
+              userAuthority: investor.keypair.publicKey,
+              userRedeemable: investor.redeemable,
+              idoAccount,
+              redeemableMint,
+              systemProgram: anchor.web3.SystemProgram.programId,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            },
+          }),
+        ],
+      });
+
+      // Give the price some time to drop before the next bid.
+      await sleep(5000);
+    }
+
+    const [depositLedger0] = await findDepositLedgerAddress(
+      idoName,
+      investors[0].keypair.publicKey,
+      program.programId
+    );
+    const [depositLedger1] = await findDepositLedgerAddress(
+      idoName,
+      investors[1].keypair.publicKey,
+      program.programId
+    );
+    const firstBid = await program.account.depositLedger.fetch(depositLedger0);
+    const secondBid = await program.account.depositLedger.fetch(depositLedger1);
+
+    // The same USDC bid later buys more watermelon.
+    assert.ok(secondBid.tokensBid.gt(firstBid.tokensBid));
+    assert.ok(
+      firstBid.tokensBid.gte(
+        investors[0].deposit.mul(PRICE_SCALE).div(startPrice)
+      )
+    );
+  });
+
+  it("should not withdraw an auction bid", async () => {
+    const investor = investors[0];
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.keypair.publicKey,
+      program.programId
+    );
+
+    await assert.rejects(
+      program.rpc.exchangeRedeemableForUsdc(investor.deposit, {
+        accounts: {
+          userAuthority: investor.keypair.publicKey,
+          idoAccount,
+          userUsdc: investor.usdc,
+          userRedeemable: investor.redeemable,
+          poolUsdc,
+          redeemableMint,
+          usdcMint,
+          depositLedger,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [investor.keypair],
+      }),
+      (err: any) => err.msg === "Auction bids can't be withdrawn"
+    );
+  });
+
+  async function claim(investor) {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    const userWatermelon = await createATA(
+      investor.keypair,
+      watermelonMint,
+      provider
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.keypair.publicKey,
+      program.programId
+    );
+    const { tokensBid } = await program.account.depositLedger.fetch(
+      depositLedger
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      investor.keypair.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeRedeemableForWatermelon(investor.deposit, {
+      accounts: {
+        userAuthority: investor.keypair.publicKey,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        userRedeemable: investor.redeemable,
+        userWatermelon,
+        userUsdc: investor.usdc,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor.keypair],
+    });
+
+    const userWatermelonInfo = await getTokenAccount(provider, userWatermelon);
+    assert.ok(userWatermelonInfo.amount.eq(tokensBid));
+
+    // Whatever was paid above the clearing price comes back.
+    const cost = tokensBid
+      .mul(floorPrice)
+      .add(PRICE_SCALE.subn(1))
+      .div(PRICE_SCALE);
+    const userUsdcInfo = await getTokenAccount(provider, investor.usdc);
+    assert.ok(userUsdcInfo.amount.eq(investor.deposit.sub(cost)));
+
+    return cost;
+  }
+
+  let proceeds = new anchor.BN(0);
+  it("should clear every bid at the floor price", async () => {
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    for (const investor of investors) {
+      proceeds = proceeds.add(await claim(investor));
+    }
+  });
+
+  it("should withdraw the auction proceeds", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    await program.rpc.withdrawPoolUsdc({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userUsdc: idoAuthorityUsdc,
+        idoAccount,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(idoAuthorityUsdcInfo.amount.eq(proceeds));
+  });
+});
@@ -0,0 +1,232 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  createATA,
+  findVestingAddress,
+  createIdo,
+} from "./utils";
+
+describe("launchpad fixed-price sale", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  // 1.5 USDC per watermelon, 4.5 USDC buys the whole public allocation.
+  const watermelonIdoPublicAmount = new anchor.BN(3_000_000);
+  const fixedPrice = new anchor.BN(1_500_000);
+  const PRICE_SCALE = new anchor.BN(1_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityWatermelon: anchor.web3.PublicKey;
+
+  let idoTimes;
+  let idoName: string;
+  it("should initialize fixed-price pool", async () => {
+    ({
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: { saleMode: { fixedPrice: {} }, fixedPrice },
+    }));
+  });
+
+  const investor = anchor.web3.Keypair.generate();
+  const deposit = new anchor.BN(3_000_000);
+  const bought = deposit.mul(PRICE_SCALE).div(fixedPrice);
+  let investorUsdc: anchor.web3.PublicKey;
+  let investorRedeemable: anchor.web3.PublicKey;
+
+  async function exchangeUsdcForRedeemable(amount: anchor.BN) {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForRedeemable(amount, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        userUsdc: investorUsdc,
+        userRedeemable: investorRedeemable,
+        usdcMint,
+        redeemableMint,
+        poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+    });
+  }
+
+  it("should deposit USDC at a fixed price", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    [investorRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        investor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    investorUsdc = await createATA(investor, usdcMint, provider, true);
+    await usdcMintAccount.mintTo(
+      investorUsdc,
+      provider.wallet.publicKey,
+      [],
+      deposit.muln(2).toNumber()
+    );
+
+    await program.rpc.initUserRedeemable({
+      accounts: {
+        userAuthority: investor.publicKey,
+        userRedeemable: investorRedeemable,
+        idoAccount,
+        redeemableMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+      signers: [investor],
+    });
+
+    await exchangeUsdcForRedeemable(deposit);
+  });
+
+  it("should not deposit once the public allocation is sold out", async () => {
+    await assert.rejects(
+      exchangeUsdcForRedeemable(deposit),
+      (err: any) => err.msg === "Public allocation is sold out"
+    );
+  });
+
+  it("should exchange redeemable for watermelon at the fixed price", async () => {
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    const investorWatermelon = await createATA(
+      investor,
+      watermelonMint,
+      provider
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeRedeemableForWatermelon(deposit, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        userRedeemable: investorRedeemable,
+        userWatermelon: investorWatermelon,
+        userUsdc: investorUsdc,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+    });
+
+    const investorWatermelonInfo = await getTokenAccount(
+      provider,
+      investorWatermelon
+    );
+    assert.ok(investorWatermelonInfo.amount.eq(bought));
+  });
+
+  it("should reclaim unsold watermelon", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.withdrawUnsoldWatermelon({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userWatermelon: idoAuthorityWatermelon,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(
+      idoAuthorityWatermelonInfo.amount.eq(
+        watermelonIdoPublicAmount.sub(bought)
+      )
+    );
+  });
+});
@@ -0,0 +1,193 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  createTokenAccount,
+  findRelatedProgramAddress,
+  createATA,
+  createIdo,
+} from "./utils";
+
+describe("launchpad liquidity bootstrapping pool", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(10_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+  let idoAuthorityWatermelon: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize liquidity bootstrapping pool", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: {
+        saleMode: { lbp: {} },
+        // Watermelon starts heavy and gets cheaper as its weight drops.
+        lbp: {
+          startWeight: new anchor.BN(9_000),
+          endWeight: new anchor.BN(5_000),
+          virtualUsdc: new anchor.BN(1_000_000),
+        },
+      },
+    }));
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+  });
+
+  const buyer = anchor.web3.Keypair.generate();
+  // Half the virtual USDC balance, the most a single swap can take.
+  const amountIn = new anchor.BN(500_000);
+  let buyerUsdc: anchor.web3.PublicKey;
+  let buyerWatermelon: anchor.web3.PublicKey;
+
+  async function swap(minAmountOut: anchor.BN, amount = amountIn) {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.swap(amount, minAmountOut, {
+      accounts: {
+        userAuthority: buyer.publicKey,
+        idoAccount,
+        userUsdc: buyerUsdc,
+        userWatermelon: buyerWatermelon,
+        poolUsdc,
+        poolWatermelon,
+        usdcMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [buyer],
+    });
+  }
+
+  it("should not swap below the minimum amount out", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    buyerUsdc = await createATA(buyer, usdcMint, provider, true);
+    buyerWatermelon = await createATA(buyer, watermelonMint, provider);
+    await usdcMintAccount.mintTo(
+      buyerUsdc,
+      provider.wallet.publicKey,
+      [],
+      amountIn.toNumber()
+    );
+
+    await assert.rejects(
+      swap(watermelonIdoPublicAmount),
+      (err: any) => err.msg === "Swap output is below the minimum"
+    );
+    await assert.rejects(
+      swap(new anchor.BN(1), amountIn.addn(1)),
+      (err: any) => err.msg === "Swap is over half the pool's USDC balance"
+    );
+  });
+
+  let bought: anchor.BN;
+  it("should swap USDC for watermelon", async () => {
+    await swap(new anchor.BN(1));
+
+    const buyerUsdcInfo = await getTokenAccount(provider, buyerUsdc);
+    assert.ok(buyerUsdcInfo.amount.eq(new anchor.BN(0)));
+
+    const buyerWatermelonInfo = await getTokenAccount(
+      provider,
+      buyerWatermelon
+    );
+    bought = buyerWatermelonInfo.amount;
+    assert.ok(bought.gtn(0));
+    assert.ok(bought.lt(watermelonIdoPublicAmount));
+  });
+
+  it("should withdraw the swap proceeds and unsold watermelon", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.withdrawPoolUsdc({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userUsdc: idoAuthorityUsdc,
+        idoAccount,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(idoAuthorityUsdcInfo.amount.eq(amountIn));
+
+    await program.rpc.withdrawUnsoldWatermelon({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userWatermelon: idoAuthorityWatermelon,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(
+      idoAuthorityWatermelonInfo.amount.eq(watermelonIdoPublicAmount.sub(bought))
+    );
+  });
+});
@@ -0,0 +1,283 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  hash,
+  getTokenAccount,
+  findRelatedProgramAddress,
+  findWhitelistLedgerAddress,
+  findVestingAddress,
+  findLotteryTicketAddress,
+  createATA,
+  requestAirdrop,
+  createIdo,
+} from "./utils";
+
+describe("launchpad lottery tier", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  // Two winning tickets of 1 watermelon each, sold at 1 USDC per watermelon.
+  const ticketSize = new anchor.BN(1_000_000);
+  const tierIdoAmount = new anchor.BN(2_000_000);
+  const tierTargetInvestment = new anchor.BN(2_000_000);
+  const LOTTERY_TIER = 0;
+
+  const seed = anchor.web3.Keypair.generate().publicKey.toBuffer();
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  // Three tickets for two winning spots.
+  const entrants = [0, 1, 2].map(() => ({
+    keypair: anchor.web3.Keypair.generate(),
+    usdc: undefined as anchor.web3.PublicKey,
+    watermelon: undefined as anchor.web3.PublicKey,
+  }));
+
+  let idoTimes;
+  let idoName: string;
+  let endRegistration: anchor.BN;
+  it("should initialize pool with a lottery tier", async () => {
+    const nowBn = new anchor.BN(Date.now() / 1000);
+    endRegistration = nowBn.addn(15);
+    ({ idoTimes, idoName, usdcMintAccount, usdcMint, watermelonMint } =
+      await createIdo(program, {
+        numIdoTokensPublic: watermelonIdoPublicAmount,
+        params: {
+          lottery: {
+            seedHash: [...hash(seed)],
+            ticketSize,
+            endRegistration,
+            endDraw: nowBn.addn(25),
+          },
+        },
+        times: { startIdo: 5, endWhitelisted: 30, endDeposits: 35, endIdo: 36 },
+        tiers: [
+          {
+            targetInvestment: tierTargetInvestment,
+            numIdoTokens: tierIdoAmount,
+            allocation: { lottery: {} },
+          },
+        ],
+      }));
+
+    for (const entrant of entrants) {
+      entrant.usdc = await createATA(entrant.keypair, usdcMint, provider, true);
+      entrant.watermelon = await createATA(
+        entrant.keypair,
+        watermelonMint,
+        provider
+      );
+      await usdcMintAccount.mintTo(
+        entrant.usdc,
+        provider.wallet.publicKey,
+        [],
+        ticketSize.toNumber()
+      );
+    }
+  });
+
+  it("should register lottery tickets", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    for (const [i, entrant] of entrants.entries()) {
+      const [lotteryTicket] = await findLotteryTicketAddress(
+        idoName,
+        entrant.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.registerTicket({
+        accounts: {
+          userAuthority: entrant.keypair.publicKey,
+          idoAccount,
+          lotteryTicket,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [entrant.keypair],
+      });
+
+      const ticketInfo = await program.account.lotteryTicket.fetch(
+        lotteryTicket
+      );
+      assert.ok(ticketInfo.number.eq(new anchor.BN(i)));
+    }
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.lotteryTickets.eq(new anchor.BN(entrants.length)));
+  });
+
+  it("should close the lottery once registration is over", async () => {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    await assert.rejects(
+      program.rpc.closeLottery({ accounts: { idoAccount } }),
+      (err: any) => err.msg === "Lottery registration is still open"
+    );
+
+    if (Date.now() < endRegistration.toNumber() * 1000) {
+      await sleep(endRegistration.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const late = anchor.web3.Keypair.generate();
+    const [lotteryTicket] = await findLotteryTicketAddress(
+      idoName,
+      late.publicKey,
+      program.programId
+    );
+    await requestAirdrop(late.publicKey, provider);
+    await assert.rejects(
+      program.rpc.registerTicket({
+        accounts: {
+          userAuthority: late.publicKey,
+          idoAccount,
+          lotteryTicket,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [late],
+      }),
+      (err: any) => err.msg === "Lottery registration is closed"
+    );
+
+    await program.rpc.closeLottery({ accounts: { idoAccount } });
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.lotterySlot.gtn(0));
+  });
+
+  it("should draw the lottery with the committed seed", async () => {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const accounts = {
+      idoAuthority: provider.wallet.publicKey,
+      idoAccount,
+      slotHashes: anchor.web3.SYSVAR_SLOT_HASHES_PUBKEY,
+    };
+
+    await assert.rejects(
+      program.rpc.drawLottery([...hash(seed)], { accounts }),
+      (err: any) => err.msg === "Seed doesn't match the committed hash"
+    );
+
+    // The closing slot's hash is only in the sysvar once that slot is done.
+    await sleep(1000);
+    await program.rpc.drawLottery([...seed], { accounts });
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.lotteryDrawn);
+    assert.ok(
+      idoAccountInfo.lotteryOffset.lt(new anchor.BN(entrants.length))
+    );
+  });
+
+  async function purchaseAccounts(entrant) {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      entrant.keypair.publicKey,
+      LOTTERY_TIER,
+      program.programId
+    );
+    const [vesting] = await findVestingAddress(
+      idoName,
+      entrant.keypair.publicKey,
+      program.programId
+    );
+    const [lotteryTicket] = await findLotteryTicketAddress(
+      idoName,
+      entrant.keypair.publicKey,
+      program.programId
+    );
+
+    return {
+      purchase: {
+        userAuthority: entrant.keypair.publicKey,
+        idoAccount,
+        userUsdc: entrant.usdc,
+        userWatermelon: entrant.watermelon,
+        usdcMint,
+        watermelonMint,
+        poolUsdc,
+        poolWatermelon,
+        whitelistLedger,
+        vesting,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      lotteryTicket,
+    };
+  }
+
+  it("should only let winning tickets buy", async () => {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    const offset = idoAccountInfo.lotteryOffset.toNumber();
+    const winners = tierIdoAmount.div(ticketSize).toNumber();
+
+    for (const [i, entrant] of entrants.entries()) {
+      const accounts = await purchaseAccounts(entrant);
+      const rank = (i + entrants.length - offset) % entrants.length;
+
+      if (rank >= winners) {
+        await assert.rejects(
+          program.rpc.exchangeUsdcForWatermelonLottery(
+            LOTTERY_TIER,
+            ticketSize,
+            { accounts, signers: [entrant.keypair] }
+          ),
+          (err: any) => err.msg === "Ticket did not win the lottery"
+        );
+        continue;
+      }
+
+      await program.rpc.exchangeUsdcForWatermelonLottery(
+        LOTTERY_TIER,
+        ticketSize,
+        { accounts, signers: [entrant.keypair] }
+      );
+
+      const watermelonInfo = await getTokenAccount(
+        provider,
+        entrant.watermelon
+      );
+      assert.ok(watermelonInfo.amount.eq(ticketSize));
+    }
+
+    const idoAccountAfter = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountAfter.tiers[LOTTERY_TIER].sold.eq(tierIdoAmount));
+  });
+});
@@ -0,0 +1,57 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import { sleep, getTokenAccount, createIdo } from "./utils";
+
+describe("launchpad floating sale without deposits", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+
+  it("should return the public allocation nobody deposited for", async () => {
+    const {
+      idoTimes,
+      idoAccount,
+      redeemableMint,
+      poolWatermelon,
+      watermelonMint,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+    });
+
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    await program.rpc.withdrawUnsoldWatermelon({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userWatermelon: idoAuthorityWatermelon,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(idoAuthorityWatermelonInfo.amount.eq(watermelonIdoPublicAmount));
+
+    const poolWatermelonInfo = await getTokenAccount(provider, poolWatermelon);
+    assert.ok(poolWatermelonInfo.amount.eqn(0));
+  });
+});
@@ -0,0 +1,247 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  createTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  createATA,
+  findVestingAddress,
+  createIdo,
+} from "./utils";
+
+describe("launchpad overflow sale", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  // 10 USDC buys the whole public allocation, i.e. 2 USDC per watermelon.
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  const hardCap = new anchor.BN(10_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize overflow pool", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: { saleMode: { overflow: {} }, hardCap },
+    }));
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+  });
+
+  // Together they raise twice the hard cap, so half of each deposit is filled.
+  const investors = [
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      deposit: new anchor.BN(15_000_000),
+      usdc: undefined as anchor.web3.PublicKey,
+      redeemable: undefined as anchor.web3.PublicKey,
+    },
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      deposit: new anchor.BN(5_000_000),
+      usdc: undefined as anchor.web3.PublicKey,
+      redeemable: undefined as anchor.web3.PublicKey,
+    },
+  ];
+  const totalRaised = investors[0].deposit.add(investors[1].deposit);
+
+  it("should deposit USDC past the hard cap", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    for (const investor of investors) {
+      [investor.redeemable] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          investor.keypair.publicKey.toBuffer(),
+          Buffer.from(idoName),
+          Buffer.from("user_redeemable"),
+        ],
+        program.programId
+      );
+
+      investor.usdc = await createATA(
+        investor.keypair,
+        usdcMint,
+        provider,
+        true
+      );
+      await usdcMintAccount.mintTo(
+        investor.usdc,
+        provider.wallet.publicKey,
+        [],
+        investor.deposit.toNumber()
+      );
+
+      const [depositLedger] = await findDepositLedgerAddress(
+        idoName,
+        investor.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.exchangeUsdcForRedeemable(investor.deposit, {
+        accounts: {
+          userAuthority: investor.keypair.publicKey,
+          idoAccount,
+          userUsdc: investor.usdc,
+          userRedeemable: investor.redeemable,
+          usdcMint,
+          redeemableMint,
+          poolUsdc,
+          depositLedger,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [investor.keypair],
+        preInstructions: [
+          program.instruction.initUserRedeemable({
+            accounts: {
+              userAuthority: investor.keypair.publicKey,
+              userRedeemable: investor.redeemable,
+              idoAccount,
+              redeemableMint,
+              systemProgram: anchor.web3.SystemProgram.programId,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            },
+          }),
+        ],
+      });
+    }
+  });
+
+  async function claim(investor) {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    const userWatermelon = await createATA(
+      investor.keypair,
+      watermelonMint,
+      provider
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.keypair.publicKey,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      investor.keypair.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeRedeemableForWatermelon(investor.deposit, {
+      accounts: {
+        userAuthority: investor.keypair.publicKey,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        userRedeemable: investor.redeemable,
+        userWatermelon,
+        userUsdc: investor.usdc,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor.keypair],
+    });
+
+    const userWatermelonInfo = await getTokenAccount(provider, userWatermelon);
+    assert.ok(
+      userWatermelonInfo.amount.eq(
+        investor.deposit.mul(watermelonIdoPublicAmount).div(totalRaised)
+      )
+    );
+
+    const userUsdcInfo = await getTokenAccount(provider, investor.usdc);
+    assert.ok(
+      userUsdcInfo.amount.eq(
+        investor.deposit.mul(totalRaised.sub(hardCap)).div(totalRaised)
+      )
+    );
+  }
+
+  it("should fill pro-rata and refund the excess", async () => {
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    await claim(investors[0]);
+  });
+
+  it("should keep unclaimed refunds in the pool", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    await program.rpc.withdrawPoolUsdc({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userUsdc: idoAuthorityUsdc,
+        idoAccount,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    // The ido authority only gets the hard cap.
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(idoAuthorityUsdcInfo.amount.eq(hardCap));
+
+    await claim(investors[1]);
+  });
+});
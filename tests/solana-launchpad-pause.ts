@@ -0,0 +1,100 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  findRelatedProgramAddress,
+  requestAirdrop,
+  createIdo,
+} from "./utils";
+
+describe("launchpad pool pause", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize pool", async () => {
+    ({ launchpadConfig, idoTimes, idoName } = await createIdo(program, {
+      times: { startIdo: 5, endWhitelisted: 6, endDeposits: 20, endIdo: 21 },
+    }));
+  });
+
+  const investor = anchor.web3.Keypair.generate();
+  async function initUserRedeemable() {
+    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [userRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        investor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    await program.rpc.initUserRedeemable({
+      accounts: {
+        userAuthority: investor.publicKey,
+        userRedeemable,
+        idoAccount,
+        redeemableMint,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+      signers: [investor],
+    });
+  }
+
+  it("should reject every phase while paused", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+    await requestAirdrop(investor.publicKey, provider);
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const accounts = {
+      authority: provider.wallet.publicKey,
+      launchpadConfig,
+      idoAccount,
+    };
+
+    // Only the ido authority or the launchpad admin can pause.
+    await assert.rejects(
+      program.rpc.pausePool({
+        accounts: { ...accounts, authority: investor.publicKey },
+        signers: [investor],
+      }),
+      (err: any) => err.msg === "Unauthorized"
+    );
+
+    await program.rpc.pausePool({ accounts });
+    let idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.paused);
+
+    await assert.rejects(
+      initUserRedeemable(),
+      (err: any) => err.msg === "IDO is paused"
+    );
+
+    await program.rpc.unpausePool({ accounts });
+    idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(!idoAccountInfo.paused);
+
+    await initUserRedeemable();
+  });
+});
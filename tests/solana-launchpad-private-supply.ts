@@ -0,0 +1,158 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+import { MerkleTree } from "merkletreejs";
+import keccak256 from "keccak256";
+
+import {
+  sleep,
+  whitelistLeaf,
+  getProof,
+  getTokenAccount,
+  findRelatedProgramAddress,
+  findWhitelistLedgerAddress,
+  findVestingAddress,
+  createATA,
+  createIdo,
+} from "./utils";
+
+describe("launchpad private supply", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  const tierIdoAmount = new anchor.BN(1_000_000);
+  const tierTargetInvestment = new anchor.BN(1_000_000);
+  // The whitelist allows more than the whole private allocation.
+  const whitelistMaxAmount = new anchor.BN(2_000_000);
+  const MERKLE_TIER = 0;
+
+  const buyer = anchor.web3.Keypair.generate();
+
+  const tree = new MerkleTree(
+    [buyer, anchor.web3.Keypair.generate()].map((acc) =>
+      whitelistLeaf(acc.publicKey, whitelistMaxAmount)
+    ),
+    keccak256,
+    { sortPairs: true }
+  );
+  const root = Buffer.from(tree.getHexRoot().slice(2), "hex");
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let buyerUsdc: anchor.web3.PublicKey;
+  let buyerWatermelon: anchor.web3.PublicKey;
+
+  let idoTimes;
+  let idoName: string;
+  it("should initialize pool with a whitelist tier", async () => {
+    ({ idoTimes, idoName, usdcMintAccount, usdcMint, watermelonMint } =
+      await createIdo(program, {
+        numIdoTokensPublic: watermelonIdoPublicAmount,
+        times: { startIdo: 5, endWhitelisted: 20, endDeposits: 22, endIdo: 23 },
+        tiers: [
+          {
+            merkleRoot: [...root],
+            targetInvestment: tierTargetInvestment,
+            numIdoTokens: tierIdoAmount,
+            allocation: { merkle: {} },
+          },
+        ],
+      }));
+
+    buyerUsdc = await createATA(buyer, usdcMint, provider, true);
+    buyerWatermelon = await createATA(buyer, watermelonMint, provider);
+    await usdcMintAccount.mintTo(
+      buyerUsdc,
+      provider.wallet.publicKey,
+      [],
+      whitelistMaxAmount.toNumber()
+    );
+  });
+
+  async function buy(amountOut: anchor.BN) {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      buyer.publicKey,
+      MERKLE_TIER,
+      program.programId
+    );
+    const [vesting] = await findVestingAddress(
+      idoName,
+      buyer.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForWatermelon(
+      MERKLE_TIER,
+      getProof(tree, buyer.publicKey, whitelistMaxAmount),
+      whitelistMaxAmount,
+      amountOut,
+      {
+        accounts: {
+          userAuthority: buyer.publicKey,
+          idoAccount,
+          userUsdc: buyerUsdc,
+          userWatermelon: buyerWatermelon,
+          usdcMint,
+          watermelonMint,
+          poolUsdc,
+          poolWatermelon,
+          whitelistLedger,
+          vesting,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [buyer],
+      }
+    );
+  }
+
+  it("should not sell more than the private allocation", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    // Within the whitelist allocation, but past the private one.
+    await assert.rejects(
+      buy(tierIdoAmount.addn(500_000)),
+      (err: any) => err.msg === "Private allocation is sold out"
+    );
+
+    await buy(tierIdoAmount);
+
+    await assert.rejects(
+      buy(new anchor.BN(1)),
+      (err: any) => err.msg === "Private allocation is sold out"
+    );
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.privateSold.eq(tierIdoAmount));
+
+    const buyerWatermelonInfo = await getTokenAccount(
+      provider,
+      buyerWatermelon
+    );
+    assert.ok(buyerWatermelonInfo.amount.eq(tierIdoAmount));
+  });
+});
@@ -0,0 +1,224 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+import { faker } from "@faker-js/faker";
+
+import {
+  sleep,
+  getTokenAccount,
+  createMint,
+  createTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  createATA,
+  initLaunchpad,
+  createIdo,
+} from "./utils";
+
+describe("launchpad protocol fee", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  const deposit = new anchor.BN(10_000_000);
+  const feeBps = new anchor.BN(250);
+
+  const treasury = anchor.web3.Keypair.generate();
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+  let treasuryUsdc: anchor.web3.PublicKey;
+
+  async function updateLaunchpad(
+    treasury: anchor.web3.PublicKey,
+    feeBps: anchor.BN,
+    quoteMints: anchor.web3.PublicKey[]
+  ) {
+    await program.rpc.updateLaunchpad(
+      provider.wallet.publicKey,
+      treasury,
+      feeBps,
+      quoteMints,
+      false,
+      {
+        accounts: {
+          admin: provider.wallet.publicKey,
+          launchpadConfig,
+        },
+      }
+    );
+  }
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  it("Initializes the state-of-the-world", async () => {
+    launchpadConfig = await initLaunchpad(program);
+
+    usdcMintAccount = await createMint(provider);
+    usdcMint = usdcMintAccount.publicKey;
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+    treasuryUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      treasury.publicKey
+    );
+  });
+
+  let idoTimes;
+  let idoName = faker.name.firstName().slice(0, 10);
+  async function initializePool() {
+    ({ idoTimes } = await createIdo(program, {
+      idoName,
+      usdcMint,
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      times: { startIdo: 5, endWhitelisted: 6, endDeposits: 12, endIdo: 13 },
+    }));
+  }
+
+  it("should cap the protocol fee", async () => {
+    await assert.rejects(
+      updateLaunchpad(treasury.publicKey, new anchor.BN(1_001), []),
+      (err: any) => err.msg === "Launchpad config is invalid"
+    );
+  });
+
+  it("should only raise in allowed quote mints", async () => {
+    const otherMint = (await createMint(provider)).publicKey;
+    await updateLaunchpad(provider.wallet.publicKey, new anchor.BN(0), [
+      otherMint,
+    ]);
+
+    await assert.rejects(
+      initializePool(),
+      (err: any) => err.msg === "Quote mint is not allowed by the launchpad"
+    );
+
+    // The pool keeps the fee in force when it is created.
+    await updateLaunchpad(treasury.publicKey, feeBps, [usdcMint]);
+    await initializePool();
+    // Other test files rely on the defaults `initLaunchpad` creates.
+    await updateLaunchpad(provider.wallet.publicKey, new anchor.BN(0), []);
+  });
+
+  it("should deposit USDC for redeemable", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const investor = anchor.web3.Keypair.generate();
+    const [userRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        investor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    const userUsdc = await createATA(investor, usdcMint, provider, true);
+    await usdcMintAccount.mintTo(
+      userUsdc,
+      provider.wallet.publicKey,
+      [],
+      deposit.toNumber()
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForRedeemable(deposit, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        userUsdc,
+        userRedeemable,
+        usdcMint,
+        redeemableMint,
+        poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+      preInstructions: [
+        program.instruction.initUserRedeemable({
+          accounts: {
+            userAuthority: investor.publicKey,
+            userRedeemable,
+            idoAccount,
+            redeemableMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+        }),
+      ],
+    });
+  });
+
+  it("should split the proceeds with the treasury", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    // Raising the fee after the sale doesn't change what the pool pays.
+    await updateLaunchpad(treasury.publicKey, new anchor.BN(1_000), []);
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const accounts = {
+      payer: provider.wallet.publicKey,
+      userAuthority: provider.wallet.publicKey,
+      userUsdc: idoAuthorityUsdc,
+      idoAccount,
+      poolUsdc,
+      redeemableMint,
+      usdcMint,
+      launchpadConfig,
+      treasuryUsdc,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    // Fees are only paid to the configured treasury.
+    await assert.rejects(
+      program.rpc.withdrawPoolUsdc({
+        accounts: { ...accounts, treasuryUsdc: idoAuthorityUsdc },
+      })
+    );
+
+    await program.rpc.withdrawPoolUsdc({ accounts });
+
+    await updateLaunchpad(provider.wallet.publicKey, new anchor.BN(0), []);
+
+    const fee = deposit.mul(feeBps).div(new anchor.BN(10_000));
+    const treasuryUsdcInfo = await getTokenAccount(provider, treasuryUsdc);
+    assert.ok(treasuryUsdcInfo.amount.eq(fee));
+
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(idoAuthorityUsdcInfo.amount.eq(deposit.sub(fee)));
+  });
+});
@@ -0,0 +1,66 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import * as assert from "assert";
+import { faker } from "@faker-js/faker";
+
+import {
+  findLaunchpadConfigAddress,
+  findRegistryEntryAddress,
+  initLaunchpad,
+  createIdo,
+} from "./utils";
+
+describe("launchpad ido registry", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  it("should register idos in creation order", async () => {
+    const [configAddress] = await findLaunchpadConfigAddress(
+      program.programId
+    );
+    await initLaunchpad(program);
+    const { numIdos } = await program.account.launchpadConfig.fetch(
+      configAddress
+    );
+
+    const names = [
+      faker.name.firstName().slice(0, 9) + "a",
+      faker.name.firstName().slice(0, 9) + "b",
+    ];
+    const idos = [];
+    for (const idoName of names) {
+      idos.push(await createIdo(program, { idoName }));
+    }
+
+    const configInfo = await program.account.launchpadConfig.fetch(
+      configAddress
+    );
+    assert.ok(configInfo.numIdos.eq(numIdos.addn(names.length)));
+
+    for (const [i, ido] of idos.entries()) {
+      const index = numIdos.addn(i);
+      const [registryEntry] = await findRegistryEntryAddress(
+        index,
+        program.programId
+      );
+      assert.ok(registryEntry.equals(ido.registryEntry));
+      const entryInfo = await program.account.idoRegistryEntry.fetch(
+        registryEntry
+      );
+      assert.ok(entryInfo.index.eq(index));
+      assert.ok(entryInfo.idoAccount.equals(ido.idoAccount));
+      assert.equal(
+        Buffer.from(entryInfo.idoName).toString().trim(),
+        ido.idoName
+      );
+      assert.ok(entryInfo.idoAuthority.equals(provider.wallet.publicKey));
+      assert.ok(entryInfo.usdcMint.equals(ido.usdcMint));
+      assert.ok(entryInfo.watermelonMint.equals(ido.watermelonMint));
+    }
+  });
+});
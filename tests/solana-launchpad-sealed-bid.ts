@@ -0,0 +1,557 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  createTokenAccount,
+  findRelatedProgramAddress,
+  findBidAddress,
+  bidCommitment,
+  createATA,
+  createIdo,
+} from "./utils";
+
+describe("launchpad sealed-bid auction", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  // Prices are USDC per PRICE_SCALE watermelon.
+  const PRICE_SCALE = new anchor.BN(1_000_000);
+  const watermelonIdoPublicAmount = new anchor.BN(10_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize sealed-bid pool", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: {
+        saleMode: { sealedBid: {} },
+        sealedBid: {
+          minPrice: new anchor.BN(1_000_000),
+          tickSize: new anchor.BN(500_000),
+        },
+      },
+      times: {
+        startIdo: 5,
+        endWhitelisted: 6,
+        endDeposits: 14,
+        endReveal: 20,
+        endIdo: 26,
+      },
+    }));
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+  });
+
+  // Demand above 1.5 USDC is 6M watermelon, so the auction clears at 1.5 USDC with
+  // the 1.5 bid filled for half of what it asked. The last bid is never revealed.
+  const bidders = [
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      price: new anchor.BN(2_000_000),
+      amount: new anchor.BN(6_000_000),
+      escrow: new anchor.BN(15_000_000),
+      fill: new anchor.BN(6_000_000),
+    },
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      price: new anchor.BN(1_500_000),
+      amount: new anchor.BN(8_000_000),
+      escrow: new anchor.BN(12_000_000),
+      fill: new anchor.BN(4_000_000),
+    },
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      price: new anchor.BN(1_500_000),
+      amount: new anchor.BN(1_000_000),
+      escrow: new anchor.BN(1_500_000),
+      fill: new anchor.BN(0),
+    },
+  ].map((bidder) => ({
+    ...bidder,
+    salt: anchor.web3.Keypair.generate().publicKey.toBuffer(),
+    usdc: undefined as anchor.web3.PublicKey,
+  }));
+  const clearingPrice = new anchor.BN(1_500_000);
+
+  it("should commit sealed bids", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    for (const bidder of bidders) {
+      bidder.usdc = await createATA(bidder.keypair, usdcMint, provider, true);
+      await usdcMintAccount.mintTo(
+        bidder.usdc,
+        provider.wallet.publicKey,
+        [],
+        bidder.escrow.toNumber()
+      );
+
+      const [bid] = await findBidAddress(
+        idoName,
+        bidder.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.commitBid(
+        [...bidCommitment(bidder.price, bidder.amount, bidder.salt)],
+        bidder.escrow,
+        {
+          accounts: {
+            userAuthority: bidder.keypair.publicKey,
+            idoAccount,
+            bid,
+            userUsdc: bidder.usdc,
+            poolUsdc,
+            usdcMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [bidder.keypair],
+        }
+      );
+    }
+  });
+
+  async function reveal(bidder, salt: Buffer) {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [bid] = await findBidAddress(
+      idoName,
+      bidder.keypair.publicKey,
+      program.programId
+    );
+
+    await program.rpc.revealBid(bidder.price, bidder.amount, [...salt], {
+      accounts: {
+        userAuthority: bidder.keypair.publicKey,
+        idoAccount,
+        bid,
+      },
+      signers: [bidder.keypair],
+    });
+  }
+
+  it("should not reveal a bid with the wrong salt", async () => {
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    await assert.rejects(
+      reveal(bidders[0], Buffer.alloc(32)),
+      (err: any) => err.msg === "Revealed bid doesn't match the commitment"
+    );
+  });
+
+  it("should reveal sealed bids", async () => {
+    await reveal(bidders[0], bidders[0].salt);
+    await reveal(bidders[1], bidders[1].salt);
+  });
+
+  it("should settle the auction at the clearing price", async () => {
+    if (Date.now() < idoTimes.endReveal.toNumber() * 1000) {
+      await sleep(idoTimes.endReveal.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    await program.rpc.settleAuction({
+      accounts: {
+        idoAccount,
+      },
+    });
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.clearingPrice.eq(clearingPrice));
+    assert.ok(idoAccountInfo.auctionSold.eq(watermelonIdoPublicAmount));
+  });
+
+  it("should pay out winning bids and refund the rest", async () => {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    for (const bidder of bidders) {
+      const [bid] = await findBidAddress(
+        idoName,
+        bidder.keypair.publicKey,
+        program.programId
+      );
+
+      const userWatermelon = await createATA(
+        bidder.keypair,
+        watermelonMint,
+        provider
+      );
+
+      await program.rpc.settleBid({
+        accounts: {
+          userAuthority: bidder.keypair.publicKey,
+          idoAccount,
+          bid,
+          userUsdc: bidder.usdc,
+          userWatermelon,
+          poolUsdc,
+          poolWatermelon,
+          usdcMint,
+          watermelonMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [bidder.keypair],
+      });
+
+      const userWatermelonInfo = await getTokenAccount(
+        provider,
+        userWatermelon
+      );
+      assert.ok(userWatermelonInfo.amount.eq(bidder.fill));
+
+      const cost = bidder.fill.mul(clearingPrice).div(PRICE_SCALE);
+      const userUsdcInfo = await getTokenAccount(provider, bidder.usdc);
+      assert.ok(userUsdcInfo.amount.eq(bidder.escrow.sub(cost)));
+
+      // The settled bid is closed.
+      assert.equal(await provider.connection.getAccountInfo(bid), null);
+    }
+  });
+
+  it("should withdraw the auction proceeds", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    await program.rpc.withdrawPoolUsdc({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userUsdc: idoAuthorityUsdc,
+        idoAccount,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(
+      idoAuthorityUsdcInfo.amount.eq(
+        watermelonIdoPublicAmount.mul(clearingPrice).div(PRICE_SCALE)
+      )
+    );
+  });
+});
+
+describe("launchpad sealed-bid auction with uneven fills", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const PRICE_SCALE = new anchor.BN(1_000_000);
+  const watermelonIdoPublicAmount = new anchor.BN(200);
+  const clearingPrice = new anchor.BN(1_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+  let idoAuthorityWatermelon: anchor.web3.PublicKey;
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+
+  // Three bids of 100 at the clearing price share 200 watermelon, each is filled
+  // for 66 and the 2 left over by the rounding go back to the ido authority.
+  const bidders = [0, 1, 2].map(() => ({
+    keypair: anchor.web3.Keypair.generate(),
+    price: clearingPrice,
+    amount: new anchor.BN(100),
+    escrow: new anchor.BN(100),
+    fill: new anchor.BN(66),
+    salt: anchor.web3.Keypair.generate().publicKey.toBuffer(),
+    usdc: undefined as anchor.web3.PublicKey,
+  }));
+
+  it("should initialize sealed-bid pool", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: {
+        saleMode: { sealedBid: {} },
+        sealedBid: {
+          minPrice: clearingPrice,
+          tickSize: new anchor.BN(500_000),
+        },
+      },
+      times: {
+        startIdo: 5,
+        endWhitelisted: 6,
+        endDeposits: 14,
+        endReveal: 20,
+        endIdo: 26,
+      },
+    }));
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+  });
+
+  it("should commit and reveal sealed bids", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    for (const bidder of bidders) {
+      bidder.usdc = await createATA(bidder.keypair, usdcMint, provider, true);
+      await usdcMintAccount.mintTo(
+        bidder.usdc,
+        provider.wallet.publicKey,
+        [],
+        bidder.escrow.toNumber()
+      );
+
+      const [bid] = await findBidAddress(
+        idoName,
+        bidder.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.commitBid(
+        [...bidCommitment(bidder.price, bidder.amount, bidder.salt)],
+        bidder.escrow,
+        {
+          accounts: {
+            userAuthority: bidder.keypair.publicKey,
+            idoAccount,
+            bid,
+            userUsdc: bidder.usdc,
+            poolUsdc,
+            usdcMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [bidder.keypair],
+        }
+      );
+    }
+
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    for (const bidder of bidders) {
+      const [bid] = await findBidAddress(
+        idoName,
+        bidder.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.revealBid(
+        bidder.price,
+        bidder.amount,
+        [...bidder.salt],
+        {
+          accounts: {
+            userAuthority: bidder.keypair.publicKey,
+            idoAccount,
+            bid,
+          },
+          signers: [bidder.keypair],
+        }
+      );
+    }
+  });
+
+  it("should settle every bid for its rounded down share", async () => {
+    if (Date.now() < idoTimes.endReveal.toNumber() * 1000) {
+      await sleep(idoTimes.endReveal.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.settleAuction({
+      accounts: {
+        idoAccount,
+      },
+    });
+
+    for (const bidder of bidders) {
+      const [bid] = await findBidAddress(
+        idoName,
+        bidder.keypair.publicKey,
+        program.programId
+      );
+
+      const userWatermelon = await createATA(
+        bidder.keypair,
+        watermelonMint,
+        provider
+      );
+
+      await program.rpc.settleBid({
+        accounts: {
+          userAuthority: bidder.keypair.publicKey,
+          idoAccount,
+          bid,
+          userUsdc: bidder.usdc,
+          userWatermelon,
+          poolUsdc,
+          poolWatermelon,
+          usdcMint,
+          watermelonMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [bidder.keypair],
+      });
+
+      const userWatermelonInfo = await getTokenAccount(
+        provider,
+        userWatermelon
+      );
+      assert.ok(userWatermelonInfo.amount.eq(bidder.fill));
+
+      const cost = bidder.fill.mul(clearingPrice).div(PRICE_SCALE);
+      const userUsdcInfo = await getTokenAccount(provider, bidder.usdc);
+      assert.ok(userUsdcInfo.amount.eq(bidder.escrow.sub(cost)));
+    }
+  });
+
+  it("should withdraw the proceeds and the rounding dust", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.withdrawPoolUsdc({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userUsdc: idoAuthorityUsdc,
+        idoAccount,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(idoAuthorityUsdcInfo.amount.eq(new anchor.BN(198)));
+
+    await program.rpc.withdrawUnsoldWatermelon({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userWatermelon: idoAuthorityWatermelon,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(idoAuthorityWatermelonInfo.amount.eq(new anchor.BN(2)));
+
+    const poolWatermelonInfo = await getTokenAccount(provider, poolWatermelon);
+    assert.ok(poolWatermelonInfo.amount.eqn(0));
+  });
+});
@@ -0,0 +1,179 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  allowlistMessage,
+  getTokenAccount,
+  findRelatedProgramAddress,
+  findWhitelistLedgerAddress,
+  findVestingAddress,
+  createATA,
+  createIdo,
+} from "./utils";
+
+describe("launchpad signed allowlist", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  const tierIdoAmount = new anchor.BN(4_000_000);
+  const tierTargetInvestment = new anchor.BN(4_000_000);
+  const maxAmount = new anchor.BN(2_000_000);
+  const SIGNED_TIER = 0;
+
+  // Stands in for the backend key that signs allocations.
+  const allowlistSigner = anchor.web3.Keypair.generate();
+  const investor = anchor.web3.Keypair.generate();
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let investorUsdc: anchor.web3.PublicKey;
+  let investorWatermelon: anchor.web3.PublicKey;
+
+  let idoTimes;
+  let idoName: string;
+  it("should initialize pool with a signature tier", async () => {
+    let idoAccount: anchor.web3.PublicKey;
+    ({
+      idoTimes,
+      idoName,
+      idoAccount,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      times: { startIdo: 5, endWhitelisted: 15, endDeposits: 20, endIdo: 21 },
+      tiers: [
+        {
+          targetInvestment: tierTargetInvestment,
+          numIdoTokens: tierIdoAmount,
+          allocation: { signature: {} },
+        },
+      ],
+    }));
+
+    investorUsdc = await createATA(investor, usdcMint, provider, true);
+    investorWatermelon = await createATA(investor, watermelonMint, provider);
+    await usdcMintAccount.mintTo(
+      investorUsdc,
+      provider.wallet.publicKey,
+      [],
+      maxAmount.toNumber()
+    );
+
+    await program.rpc.updateAllowlistSigner(allowlistSigner.publicKey, {
+      accounts: {
+        idoAuthority: provider.wallet.publicKey,
+        idoAccount,
+      },
+    });
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.allowlistSigner.equals(allowlistSigner.publicKey));
+  });
+
+  async function buySigned(signer: anchor.web3.Keypair, expiry: anchor.BN) {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      investor.publicKey,
+      SIGNED_TIER,
+      program.programId
+    );
+    const [vesting] = await findVestingAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    return program.rpc.exchangeUsdcForWatermelonSigned(
+      SIGNED_TIER,
+      maxAmount,
+      expiry,
+      maxAmount,
+      {
+        accounts: {
+          purchase: {
+            userAuthority: investor.publicKey,
+            idoAccount,
+            userUsdc: investorUsdc,
+            userWatermelon: investorWatermelon,
+            usdcMint,
+            watermelonMint,
+            poolUsdc,
+            poolWatermelon,
+            whitelistLedger,
+            vesting,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        },
+        signers: [investor],
+        preInstructions: [
+          anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: signer.secretKey,
+            message: allowlistMessage(
+              idoAccount,
+              investor.publicKey,
+              SIGNED_TIER,
+              maxAmount,
+              expiry
+            ),
+          }),
+        ],
+      }
+    );
+  }
+
+  it("should buy with a signed allocation", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const expiry = new anchor.BN(Date.now() / 1000 + 60);
+
+    await assert.rejects(
+      buySigned(anchor.web3.Keypair.generate(), expiry),
+      (err: any) => err.msg === "Allowlist signature is missing or invalid"
+    );
+
+    await assert.rejects(
+      buySigned(allowlistSigner, new anchor.BN(Date.now() / 1000 - 60)),
+      (err: any) => err.msg === "Allowlist signature has expired"
+    );
+
+    await buySigned(allowlistSigner, expiry);
+
+    const investorWatermelonInfo = await getTokenAccount(
+      provider,
+      investorWatermelon
+    );
+    assert.ok(investorWatermelonInfo.amount.eq(maxAmount));
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.tiers[SIGNED_TIER].sold.eq(maxAmount));
+  });
+});
@@ -0,0 +1,262 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  createTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  createATA,
+  findVestingAddress,
+  createIdo,
+} from "./utils";
+
+describe("launchpad soft cap", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(10_000_000);
+  const softCap = new anchor.BN(50_000_000);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoAuthorityUsdc: anchor.web3.PublicKey;
+  let idoAuthorityWatermelon: anchor.web3.PublicKey;
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize pool with a soft cap", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+      idoAuthorityWatermelon,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      params: { softCap },
+    }));
+
+    idoAuthorityUsdc = await createTokenAccount(
+      provider,
+      usdcMint,
+      provider.wallet.publicKey
+    );
+  });
+
+  const investor = anchor.web3.Keypair.generate();
+  const deposit = new anchor.BN(10_000_000);
+  let investorUsdc: anchor.web3.PublicKey;
+  let investorRedeemable: anchor.web3.PublicKey;
+  it("should deposit USDC below the soft cap", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    [investorRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        investor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    investorUsdc = await createATA(investor, usdcMint, provider, true);
+    await usdcMintAccount.mintTo(
+      investorUsdc,
+      provider.wallet.publicKey,
+      [],
+      deposit.toNumber()
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForRedeemable(deposit, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        userUsdc: investorUsdc,
+        userRedeemable: investorRedeemable,
+        usdcMint,
+        redeemableMint,
+        poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+      preInstructions: [
+        program.instruction.initUserRedeemable({
+          accounts: {
+            userAuthority: investor.publicKey,
+            userRedeemable: investorRedeemable,
+            idoAccount,
+            redeemableMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+        }),
+      ],
+    });
+  });
+
+  it("should not exchange redeemable for watermelon", async () => {
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    const investorWatermelon = await createATA(
+      investor,
+      watermelonMint,
+      provider
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await assert.rejects(
+      program.rpc.exchangeRedeemableForWatermelon(deposit, {
+        accounts: {
+          userAuthority: investor.publicKey,
+          idoAccount,
+          poolWatermelon,
+          redeemableMint,
+          watermelonMint,
+          userRedeemable: investorRedeemable,
+          userWatermelon: investorWatermelon,
+          userUsdc: investorUsdc,
+          poolUsdc,
+          usdcMint,
+          depositLedger,
+          vesting,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [investor],
+      }),
+      (err: any) => err.msg === "Soft cap was not reached"
+    );
+  });
+
+  it("should refund redeemable", async () => {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    await program.rpc.refundRedeemable(deposit, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        userUsdc: investorUsdc,
+        userRedeemable: investorRedeemable,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+    });
+
+    const investorUsdcInfo = await getTokenAccount(provider, investorUsdc);
+    assert.ok(investorUsdcInfo.amount.eq(deposit));
+
+    // The emptied redeemable account is closed.
+    assert.equal(
+      await provider.connection.getAccountInfo(investorRedeemable),
+      null
+    );
+  });
+
+  it("should reclaim all watermelon", async () => {
+    if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
+      await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.withdrawUnsoldWatermelon({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userWatermelon: idoAuthorityWatermelon,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(idoAuthorityWatermelonInfo.amount.eq(watermelonIdoPublicAmount));
+
+    // Nothing was raised, so there is no USDC for the ido authority.
+    await program.rpc.withdrawPoolUsdc({
+      accounts: {
+        payer: provider.wallet.publicKey,
+        userAuthority: provider.wallet.publicKey,
+        userUsdc: idoAuthorityUsdc,
+        idoAccount,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+    const idoAuthorityUsdcInfo = await getTokenAccount(
+      provider,
+      idoAuthorityUsdc
+    );
+    assert.ok(idoAuthorityUsdcInfo.amount.eq(new anchor.BN(0)));
+  });
+});
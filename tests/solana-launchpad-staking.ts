@@ -0,0 +1,382 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  createMint,
+  findRelatedProgramAddress,
+  findWhitelistLedgerAddress,
+  findVestingAddress,
+  findStakePoolAddress,
+  findStakeAccountAddress,
+  findStakeSnapshotAddress,
+  createATA,
+  initLaunchpad,
+  createIdo,
+} from "./utils";
+
+describe("launchpad staking tiers", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(5_000_000);
+  // The stake-weighted tier sells 4 watermelon for 4 USDC.
+  const tierIdoAmount = new anchor.BN(4_000_000);
+  const tierTargetInvestment = new anchor.BN(4_000_000);
+  const lockPeriod = new anchor.BN(1);
+  const STAKE_TIER = 0;
+
+  let stakeMintAccount: Token;
+  let stakeMint: anchor.web3.PublicKey;
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  // Staking 3:1 splits the tier 3:1.
+  const stakers = [
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      stake: new anchor.BN(3_000_000),
+      userStake: undefined as anchor.web3.PublicKey,
+      usdc: undefined as anchor.web3.PublicKey,
+      watermelon: undefined as anchor.web3.PublicKey,
+    },
+    {
+      keypair: anchor.web3.Keypair.generate(),
+      stake: new anchor.BN(1_000_000),
+      userStake: undefined as anchor.web3.PublicKey,
+      usdc: undefined as anchor.web3.PublicKey,
+      watermelon: undefined as anchor.web3.PublicKey,
+    },
+  ];
+  const totalStake = stakers[0].stake.add(stakers[1].stake);
+
+  let launchpadConfig: anchor.web3.PublicKey;
+  it("Initializes the state-of-the-world", async () => {
+    launchpadConfig = await initLaunchpad(program);
+
+    stakeMintAccount = await createMint(provider);
+    stakeMint = stakeMintAccount.publicKey;
+
+    for (const staker of stakers) {
+      staker.userStake = await createATA(
+        staker.keypair,
+        stakeMint,
+        provider,
+        true
+      );
+      await stakeMintAccount.mintTo(
+        staker.userStake,
+        provider.wallet.publicKey,
+        [],
+        staker.stake.toNumber()
+      );
+    }
+  });
+
+  it("should initialize the stake pool", async () => {
+    const [[stakePool, stakePoolBump], [stakeVault, stakeVaultBump]] =
+      await findStakePoolAddress(program.programId);
+
+    const accounts = {
+      admin: provider.wallet.publicKey,
+      launchpadConfig,
+      stakePool,
+      stakeMint,
+      stakeVault,
+      systemProgram: anchor.web3.SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    };
+    const bumps = { stakePool: stakePoolBump, stakeVault: stakeVaultBump };
+
+    // Only the launchpad admin creates the stake pool.
+    const stranger = anchor.web3.Keypair.generate();
+    await assert.rejects(
+      program.rpc.initializeStakePool(bumps, lockPeriod, {
+        accounts: { ...accounts, admin: stranger.publicKey },
+        signers: [stranger],
+      }),
+      (err: any) => err.msg === "Unauthorized"
+    );
+
+    await program.rpc.initializeStakePool(bumps, lockPeriod, { accounts });
+  });
+
+  it("should stake platform tokens", async () => {
+    const [[stakePool], [stakeVault]] = await findStakePoolAddress(
+      program.programId
+    );
+
+    for (const staker of stakers) {
+      const [stakeAccount] = await findStakeAccountAddress(
+        staker.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.stake(staker.stake, {
+        accounts: {
+          userAuthority: staker.keypair.publicKey,
+          stakePool,
+          stakeAccount,
+          userStake: staker.userStake,
+          stakeVault,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [staker.keypair],
+      });
+
+      const stakeAccountInfo = await program.account.stakeAccount.fetch(
+        stakeAccount
+      );
+      assert.ok(stakeAccountInfo.amount.eq(staker.stake));
+    }
+
+    const stakePoolInfo = await program.account.stakePool.fetch(stakePool);
+    assert.ok(stakePoolInfo.totalStaked.eq(totalStake));
+  });
+
+  let idoTimes;
+  let idoName: string;
+  let registryIndex: anchor.BN;
+  it("should initialize pool with a stake-weighted tier", async () => {
+    ({
+      idoTimes,
+      idoName,
+      registryIndex,
+      usdcMintAccount,
+      usdcMint,
+      watermelonMint,
+    } = await createIdo(program, {
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+      times: {
+        startIdo: 10,
+        endWhitelisted: 16,
+        endDeposits: 20,
+        endIdo: 21,
+      },
+      tiers: [
+        {
+          targetInvestment: tierTargetInvestment,
+          numIdoTokens: tierIdoAmount,
+          allocation: { stakeWeighted: {} },
+        },
+      ],
+    }));
+  });
+
+  it("should snapshot stake for the ido", async () => {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    for (const staker of stakers) {
+      const [stakeAccount] = await findStakeAccountAddress(
+        staker.keypair.publicKey,
+        program.programId
+      );
+      const [stakeSnapshot] = await findStakeSnapshotAddress(
+        idoName,
+        registryIndex,
+        staker.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.snapshotStake({
+        accounts: {
+          userAuthority: staker.keypair.publicKey,
+          idoAccount,
+          stakeAccount,
+          stakeSnapshot,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [staker.keypair],
+      });
+
+      const snapshotInfo = await program.account.stakeSnapshot.fetch(
+        stakeSnapshot
+      );
+      assert.ok(snapshotInfo.amount.eq(staker.stake));
+
+      // The stake stays locked until the private round is over.
+      const stakeAccountInfo = await program.account.stakeAccount.fetch(
+        stakeAccount
+      );
+      assert.ok(stakeAccountInfo.lockedUntil.eq(idoTimes.endWhitelisted));
+    }
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.totalStakeSnapshot.eq(totalStake));
+  });
+
+  it("should not unstake while the snapshot is live", async () => {
+    const [[stakePool], [stakeVault]] = await findStakePoolAddress(
+      program.programId
+    );
+    const staker = stakers[0];
+    const [stakeAccount] = await findStakeAccountAddress(
+      staker.keypair.publicKey,
+      program.programId
+    );
+
+    await assert.rejects(
+      program.rpc.unstake(staker.stake, {
+        accounts: {
+          userAuthority: staker.keypair.publicKey,
+          stakePool,
+          stakeAccount,
+          userStake: staker.userStake,
+          stakeVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [staker.keypair],
+      }),
+      (err: any) => err.msg === "Stake is still locked"
+    );
+  });
+
+  async function purchaseAccounts(staker) {
+    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      staker.keypair.publicKey,
+      STAKE_TIER,
+      program.programId
+    );
+    const [vesting] = await findVestingAddress(
+      idoName,
+      staker.keypair.publicKey,
+      program.programId
+    );
+    const [stakeSnapshot] = await findStakeSnapshotAddress(
+      idoName,
+      registryIndex,
+      staker.keypair.publicKey,
+      program.programId
+    );
+
+    return {
+      purchase: {
+        userAuthority: staker.keypair.publicKey,
+        idoAccount,
+        userUsdc: staker.usdc,
+        userWatermelon: staker.watermelon,
+        usdcMint,
+        watermelonMint,
+        poolUsdc,
+        poolWatermelon,
+        whitelistLedger,
+        vesting,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      stakeSnapshot,
+    };
+  }
+
+  it("should cap purchases by stake weight", async () => {
+    if (Date.now() < idoTimes.startIdo.toNumber() * 1000) {
+      await sleep(idoTimes.startIdo.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    for (const staker of stakers) {
+      staker.usdc = await createATA(staker.keypair, usdcMint, provider);
+      staker.watermelon = await createATA(
+        staker.keypair,
+        watermelonMint,
+        provider
+      );
+      await usdcMintAccount.mintTo(
+          staker.usdc,
+        provider.wallet.publicKey,
+        [],
+        tierTargetInvestment.toNumber()
+      );
+
+      const allocation = tierIdoAmount.mul(staker.stake).div(totalStake);
+      const accounts = await purchaseAccounts(staker);
+
+      await assert.rejects(
+        program.rpc.exchangeUsdcForWatermelonStaked(
+          STAKE_TIER,
+          allocation.add(new anchor.BN(1)),
+          { accounts, signers: [staker.keypair] }
+        ),
+        (err: any) => err.msg === "Whitelist allocation exceeded"
+      );
+
+      await program.rpc.exchangeUsdcForWatermelonStaked(
+        STAKE_TIER,
+        allocation,
+        { accounts, signers: [staker.keypair] }
+      );
+
+      const watermelonInfo = await getTokenAccount(
+        provider,
+          staker.watermelon
+      );
+      assert.ok(watermelonInfo.amount.eq(allocation));
+    }
+
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.tiers[STAKE_TIER].sold.eq(tierIdoAmount));
+  });
+
+  it("should unstake once the private round is over", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[stakePool], [stakeVault]] = await findStakePoolAddress(
+      program.programId
+    );
+
+    for (const staker of stakers) {
+      const [stakeAccount] = await findStakeAccountAddress(
+        staker.keypair.publicKey,
+        program.programId
+      );
+
+      await program.rpc.unstake(staker.stake, {
+        accounts: {
+          userAuthority: staker.keypair.publicKey,
+          stakePool,
+          stakeAccount,
+          userStake: staker.userStake,
+          stakeVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [staker.keypair],
+      });
+
+      const userStakeInfo = await getTokenAccount(provider, staker.userStake);
+      assert.ok(userStakeInfo.amount.eq(staker.stake));
+    }
+
+    const stakePoolInfo = await program.account.stakePool.fetch(stakePool);
+    assert.ok(stakePoolInfo.totalStaked.eq(new anchor.BN(0)));
+  });
+});
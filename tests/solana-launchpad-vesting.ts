@@ -0,0 +1,251 @@
+import * as anchor from "@project-serum/anchor";
+import { describe } from "mocha";
+import { Program } from "@project-serum/anchor";
+import { SolanaLaunchpad } from "../target/types/solana_launchpad";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import * as assert from "assert";
+
+import {
+  sleep,
+  getTokenAccount,
+  findRelatedProgramAddress,
+  findDepositLedgerAddress,
+  createATA,
+  findVestingAddress,
+  createIdo,
+} from "./utils";
+
+describe("launchpad vesting", async () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+
+  // @ts-ignore
+  const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
+
+  const watermelonIdoPublicAmount = new anchor.BN(10_000_000);
+  // A quarter of the public round unlocks when deposits close, the rest over
+  // the next 8 seconds.
+  const tgeUnlockBps = new anchor.BN(2_500);
+  const vestingPeriod = new anchor.BN(8);
+
+  let usdcMintAccount: Token;
+  let usdcMint: anchor.web3.PublicKey;
+  let watermelonMint: anchor.web3.PublicKey;
+
+  let idoTimes;
+  let idoName: string;
+  it("should not lock a schedule that never unlocks", async () => {
+    await assert.rejects(
+      createIdo(program, {
+        params: {
+          publicVesting: {
+            tgeUnlockBps,
+            cliff: new anchor.BN(0),
+            period: new anchor.BN(0),
+          },
+        },
+      }),
+      (err: any) => err.msg === "Vesting schedule is invalid"
+    );
+  });
+
+  it("should initialize pool with vesting", async () => {
+    ({ idoTimes, idoName, usdcMintAccount, usdcMint, watermelonMint } =
+      await createIdo(program, {
+        numIdoTokensPublic: watermelonIdoPublicAmount,
+        params: {
+          publicVesting: {
+            tgeUnlockBps,
+            cliff: new anchor.BN(0),
+            period: vestingPeriod,
+          },
+        },
+      }));
+  });
+
+  const investor = anchor.web3.Keypair.generate();
+  const deposit = new anchor.BN(10_000_000);
+  let investorUsdc: anchor.web3.PublicKey;
+  let investorRedeemable: anchor.web3.PublicKey;
+  it("should deposit USDC for redeemable", async () => {
+    if (Date.now() < idoTimes.endWhitelisted.toNumber() * 1000) {
+      await sleep(
+        idoTimes.endWhitelisted.toNumber() * 1000 - Date.now() + 2000
+      );
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    [investorRedeemable] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        investor.publicKey.toBuffer(),
+        Buffer.from(idoName),
+        Buffer.from("user_redeemable"),
+      ],
+      program.programId
+    );
+
+    investorUsdc = await createATA(investor, usdcMint, provider, true);
+    await usdcMintAccount.mintTo(
+      investorUsdc,
+      provider.wallet.publicKey,
+      [],
+      deposit.toNumber()
+    );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeUsdcForRedeemable(deposit, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        userUsdc: investorUsdc,
+        userRedeemable: investorRedeemable,
+        usdcMint,
+        redeemableMint,
+        poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+      preInstructions: [
+        program.instruction.initUserRedeemable({
+          accounts: {
+            userAuthority: investor.publicKey,
+            userRedeemable: investorRedeemable,
+            idoAccount,
+            redeemableMint,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+        }),
+      ],
+    });
+  });
+
+  let investorWatermelon: anchor.web3.PublicKey;
+  let vesting: anchor.web3.PublicKey;
+  it("should lock claimed watermelon in vesting", async () => {
+    if (Date.now() < idoTimes.endDeposits.toNumber() * 1000) {
+      await sleep(idoTimes.endDeposits.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    investorWatermelon = await createATA(investor, watermelonMint, provider);
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+    [vesting] = await findVestingAddress(
+      idoName,
+      investor.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeRedeemableForWatermelon(deposit, {
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        poolWatermelon,
+        redeemableMint,
+        watermelonMint,
+        userRedeemable: investorRedeemable,
+        userWatermelon: investorWatermelon,
+        userUsdc: investorUsdc,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+      preInstructions: [
+        program.instruction.initVesting({
+          accounts: {
+            userAuthority: investor.publicKey,
+            idoAccount,
+            vesting,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+        }),
+      ],
+    });
+
+    const investorWatermelonInfo = await getTokenAccount(
+      provider,
+      investorWatermelon
+    );
+    assert.ok(investorWatermelonInfo.amount.eq(new anchor.BN(0)));
+
+    const vestingInfo = await program.account.vesting.fetch(vesting);
+    assert.ok(vestingInfo.publicTotal.eq(watermelonIdoPublicAmount));
+    assert.ok(vestingInfo.publicReleased.eq(new anchor.BN(0)));
+    assert.ok(vestingInfo.privateTotal.eq(new anchor.BN(0)));
+  });
+
+  async function claimVested() {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    await program.rpc.claimVested({
+      accounts: {
+        userAuthority: investor.publicKey,
+        idoAccount,
+        vesting,
+        userWatermelon: investorWatermelon,
+        poolWatermelon,
+        watermelonMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [investor],
+    });
+
+    return (await getTokenAccount(provider, investorWatermelon)).amount;
+  }
+
+  it("should release the TGE unlock and what has vested", async () => {
+    const released = await claimVested();
+    assert.ok(
+      released.gte(watermelonIdoPublicAmount.mul(tgeUnlockBps).divn(10_000))
+    );
+    assert.ok(released.lt(watermelonIdoPublicAmount));
+  });
+
+  it("should release everything once vested", async () => {
+    const vestingEnd = idoTimes.endDeposits.add(vestingPeriod);
+    if (Date.now() < vestingEnd.toNumber() * 1000) {
+      await sleep(vestingEnd.toNumber() * 1000 - Date.now() + 2000);
+    }
+
+    const released = await claimVested();
+    assert.ok(released.eq(watermelonIdoPublicAmount));
+
+    await assert.rejects(
+      claimVested(),
+      (err: any) => err.msg === "No vested tokens to claim"
+    );
+  });
+});
@@ -2,19 +2,20 @@ import * as anchor from "@project-serum/anchor";
 import { describe } from "mocha";
 import { Program } from "@project-serum/anchor";
 import { SolanaLaunchpad } from "../target/types/solana_launchpad";
-import { Token, TOKEN_PROGRAM_ID, NATIVE_MINT } from "@solana/spl-token";
+import { TOKEN_PROGRAM_ID, NATIVE_MINT } from "@solana/spl-token";
 import * as assert from "assert";
-import { faker } from "@faker-js/faker";
 
 import {
   sleep,
   getTokenAccount,
-  createMint,
   createTokenAccount,
   createWrapNativeAccount,
   findRelatedProgramAddress,
+  findDepositLedgerAddress,
   createATA,
   requestAirdrop,
+  findVestingAddress,
+  createIdo,
 } from "./utils";
 
 describe("launchpad wsol", async () => {
@@ -24,99 +25,33 @@ describe("launchpad wsol", async () => {
   // @ts-ignore
   const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
 
-  const privateTargetInvestment = new anchor.BN(100_000_000_000);
-  const watermelonIdoPrivateAmount = new anchor.BN(2_000_000_000_000);
+  // Public sale only, no whitelist tiers.
   const watermelonIdoPublicAmount = new anchor.BN(10_000_000_000_000);
 
-  const totalWatermelonIdoAmount = watermelonIdoPrivateAmount.add(
-    watermelonIdoPublicAmount
-  );
-
   let usdcMint: anchor.web3.PublicKey = NATIVE_MINT;
-  let watermelonMintAccount: Token;
   let watermelonMint: anchor.web3.PublicKey;
 
   let idoAuthorityUsdc: anchor.web3.PublicKey;
-  let idoAuthorityWatermelon: anchor.web3.PublicKey;
 
-  it("Initializes the state-of-the-world", async () => {
-    watermelonMintAccount = await createMint(provider);
-    watermelonMint = watermelonMintAccount.publicKey;
+  let launchpadConfig: anchor.web3.PublicKey;
+  let idoTimes;
+  let idoName: string;
+  it("should initialize WSOL pool", async () => {
+    ({
+      launchpadConfig,
+      idoTimes,
+      idoName,
+      watermelonMint,
+    } = await createIdo(program, {
+      usdcMint,
+      numIdoTokensPublic: watermelonIdoPublicAmount,
+    }));
 
     idoAuthorityUsdc = await createTokenAccount(
       provider,
       usdcMint,
       provider.wallet.publicKey
     );
-
-    idoAuthorityWatermelon = await createTokenAccount(
-      provider,
-      watermelonMint,
-      provider.wallet.publicKey
-    );
-
-    await watermelonMintAccount.mintTo(
-      idoAuthorityWatermelon,
-      provider.wallet.publicKey,
-      [],
-      totalWatermelonIdoAmount.toNumber()
-    );
-  });
-
-  let idoTimes;
-  let idoName = faker.name.firstName().slice(0, 10);
-  it("should initialize WSOL pool", async () => {
-    const [
-      [idoAccount, idoAccountBump],
-      [redeemableMint, redeemableMintBump],
-      [poolUsdc, poolUsdcBump],
-    ] = await findRelatedProgramAddress(idoName, program.programId);
-
-    const [poolWatermelon, poolWatermelonBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [Buffer.from(idoName), Buffer.from("pool_watermelon")],
-        program.programId
-      );
-
-    let bumps = new PoolBumps();
-    bumps.idoAccount = idoAccountBump;
-    bumps.redeemableMint = redeemableMintBump;
-    bumps.poolWatermelon = poolWatermelonBump;
-    bumps.poolUsdc = poolUsdcBump;
-
-    // Initialize ido times
-    idoTimes = new IdoTimes();
-
-    const nowBn = new anchor.BN(Date.now() / 1000);
-    idoTimes.startIdo = nowBn.add(new anchor.BN(5));
-    idoTimes.endWhitelisted = nowBn.add(new anchor.BN(6));
-    idoTimes.endDeposits = nowBn.add(new anchor.BN(15));
-    idoTimes.endIdo = nowBn.add(new anchor.BN(16));
-
-    await program.rpc.initializePool(
-      idoName,
-      bumps,
-      privateTargetInvestment,
-      watermelonIdoPublicAmount,
-      watermelonIdoPrivateAmount,
-      idoTimes,
-      null,
-      {
-        accounts: {
-          idoAuthority: provider.wallet.publicKey,
-          idoAuthorityWatermelon,
-          idoAccount,
-          redeemableMint,
-          watermelonMint,
-          poolWatermelon,
-          usdcMint,
-          poolUsdc,
-          systemProgram: anchor.web3.SystemProgram.programId,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-        },
-      }
-    );
   });
 
   const investor1 = anchor.web3.Keypair.generate();
@@ -151,6 +86,12 @@ describe("launchpad wsol", async () => {
       program.programId
     );
 
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor1.publicKey,
+      program.programId
+    );
+
     await program.rpc.exchangeUsdcForRedeemable(firstDeposit, {
       accounts: {
         userAuthority: investor1.publicKey,
@@ -160,6 +101,8 @@ describe("launchpad wsol", async () => {
         usdcMint,
         redeemableMint,
         poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
       signers: [investor1],
@@ -185,10 +128,8 @@ describe("launchpad wsol", async () => {
     if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
       await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
     }
-    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
-      idoName,
-      program.programId
-    );
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
 
     userWatermelon = await createATA(investor1, watermelonMint, provider);
 
@@ -206,6 +147,18 @@ describe("launchpad wsol", async () => {
       program.programId
     );
 
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      investor1.publicKey,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      investor1.publicKey,
+      program.programId
+    );
+
     await program.rpc.exchangeRedeemableForWatermelon(firstDeposit, {
       accounts: {
         userAuthority: investor1.publicKey,
@@ -215,6 +168,11 @@ describe("launchpad wsol", async () => {
         watermelonMint,
         userRedeemable,
         userWatermelon,
+        userUsdc: investor1WSol,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
       signers: [investor1],
@@ -233,10 +191,8 @@ describe("launchpad wsol", async () => {
   });
 
   it("should withdraw", async () => {
-    const [[idoAccount], , [poolUsdc]] = await findRelatedProgramAddress(
-      idoName,
-      program.programId
-    );
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
     let userUsdcAccountInfo = await getTokenAccount(provider, idoAuthorityUsdc);
     assert.ok(userUsdcAccountInfo.amount.eq(new anchor.BN(0)));
     await program.rpc.withdrawPoolUsdc({
@@ -246,7 +202,10 @@ describe("launchpad wsol", async () => {
         userUsdc: idoAuthorityUsdc,
         idoAccount,
         poolUsdc,
+        redeemableMint,
         usdcMint,
+        launchpadConfig,
+        treasuryUsdc: idoAuthorityUsdc,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
     });
@@ -277,19 +236,4 @@ describe("launchpad wsol", async () => {
     //   investor1.publicKey
     // );
   });
-
-  function PoolBumps() {
-    this.idoAccount;
-    this.redeemableMint;
-    this.poolWatermelon;
-    this.poolUsdc;
-    this.poolNative;
-  }
-
-  function IdoTimes() {
-    this.startIdo;
-    this.endWhitelisted;
-    this.endDeposits;
-    this.endIdo;
-  }
 });
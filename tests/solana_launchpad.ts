@@ -5,9 +5,15 @@ import {
   createMint,
   createTokenAccount,
   getProof,
-  hash,
+  whitelistLeaf,
   findRelatedProgramAddress,
+  findWhitelistLedgerAddress,
+  findDepositLedgerAddress,
   createATA,
+  findVestingAddress,
+  initLaunchpad,
+  findNextRegistryEntryAddress,
+  approveIdo,
 } from "./utils";
 import { Program } from "@project-serum/anchor";
 import { SolanaLaunchpad } from "../target/types/solana_launchpad";
@@ -26,14 +32,25 @@ describe("solana-launchpad", () => {
   // @ts-ignore
   const program = anchor.workspace.SolanaLaunchpad as Program<SolanaLaunchpad>;
 
-  const privateTargetInvestment = new anchor.BN(100_000);
-  const watermelonIdoPrivateAmount = new anchor.BN(2_000_000);
+  const goldTargetInvestment = new anchor.BN(100_000);
+  const goldIdoAmount = new anchor.BN(2_000_000);
+  const silverTargetInvestment = new anchor.BN(60_000);
+  const silverIdoAmount = new anchor.BN(1_000_000);
+  const watermelonIdoPrivateAmount = goldIdoAmount.add(silverIdoAmount);
   const watermelonIdoPublicAmount = new anchor.BN(10_000_000);
 
+  const softCap = new anchor.BN(20_000_000);
+  const hardCap = new anchor.BN(40_000_000);
+  const minContribution = new anchor.BN(1_000_000);
+  const maxContribution = new anchor.BN(30_000_000);
+
   const totalWatermelonIdoAmount = watermelonIdoPrivateAmount.add(
     watermelonIdoPublicAmount
   );
 
+  const GOLD_TIER = 0;
+  const SILVER_TIER = 1;
+
   let usdcMintAccount: Token;
   let usdcMint: anchor.web3.PublicKey;
   let watermelonMintAccount: Token;
@@ -48,9 +65,16 @@ describe("solana-launchpad", () => {
     usersAcc.push(anchor.web3.Keypair.generate());
   }
 
-  const leaves = usersAcc.map((acc) => hash(acc.publicKey.toBuffer()));
+  // Every whitelisted wallet is allowed to buy up to this much watermelon.
+  const whitelistMaxAmount = new anchor.BN(300_000);
 
-  leaves.push(hash(program.provider.wallet.publicKey.toBuffer()));
+  const leaves = usersAcc.map((acc) =>
+    whitelistLeaf(acc.publicKey, whitelistMaxAmount)
+  );
+
+  leaves.push(
+    whitelistLeaf(program.provider.wallet.publicKey, whitelistMaxAmount)
+  );
 
   const tree = new MerkleTree(leaves, keccak256, { sortPairs: true });
 
@@ -59,7 +83,28 @@ describe("solana-launchpad", () => {
 
   const root = Buffer.from(rootHex.slice(2), "hex");
 
+  const silverTree = new MerkleTree(
+    [anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate()].map(
+      (acc) => whitelistLeaf(acc.publicKey, whitelistMaxAmount)
+    ),
+    keccak256,
+    { sortPairs: true }
+  );
+  const silverRoot = Buffer.from(silverTree.getHexRoot().slice(2), "hex");
+
+  // Late allowlist entry, added by rotating the root after the pool is live.
+  const lateUser = anchor.web3.Keypair.generate();
+  const lateTree = new MerkleTree(
+    [...leaves, whitelistLeaf(lateUser.publicKey, whitelistMaxAmount)],
+    keccak256,
+    { sortPairs: true }
+  );
+  const lateRoot = Buffer.from(lateTree.getHexRoot().slice(2), "hex");
+
+  let launchpadConfig: anchor.web3.PublicKey;
   it("Initializes the state-of-the-world", async () => {
+    launchpadConfig = await initLaunchpad(program);
+
     usdcMintAccount = await createMint(provider);
     watermelonMintAccount = await createMint(provider);
     usdcMint = usdcMintAccount.publicKey;
@@ -122,20 +167,52 @@ describe("solana-launchpad", () => {
     const nowBn = new anchor.BN(Date.now() / 1000);
     idoTimes.startIdo = nowBn.add(new anchor.BN(5));
     idoTimes.endWhitelisted = nowBn.add(new anchor.BN(10));
-    idoTimes.endDeposits = nowBn.add(new anchor.BN(15));
-    idoTimes.endIdo = nowBn.add(new anchor.BN(20));
+    idoTimes.endDeposits = nowBn.add(new anchor.BN(20));
+    idoTimes.endReveal = idoTimes.endDeposits;
+    idoTimes.endIdo = nowBn.add(new anchor.BN(25));
+
+    const registryEntry = await findNextRegistryEntryAddress(program);
 
     await program.rpc.initializePool(
       idoName,
       bumps,
-      privateTargetInvestment,
       watermelonIdoPublicAmount,
-      watermelonIdoPrivateAmount,
+      {
+        softCap,
+        hardCap,
+        minContribution,
+        maxContribution,
+        saleMode: { floating: {} },
+        fixedPrice: new anchor.BN(0),
+        auction: new AuctionParams(),
+        lbp: new LbpParams(),
+        sealedBid: new SealedBidParams(),
+        privateVesting: new VestingParams(),
+        publicVesting: new VestingParams(),
+        lottery: new LotteryParams(),
+      },
       idoTimes,
-      [...root],
+      [
+        {
+          merkleRoot: [...root],
+          targetInvestment: goldTargetInvestment,
+          numIdoTokens: goldIdoAmount,
+          sold: new anchor.BN(0),
+          allocation: { merkle: {} },
+        },
+        {
+          merkleRoot: [...silverRoot],
+          targetInvestment: silverTargetInvestment,
+          numIdoTokens: silverIdoAmount,
+          sold: new anchor.BN(0),
+          allocation: { merkle: {} },
+        },
+      ],
       {
         accounts: {
           idoAuthority: provider.wallet.publicKey,
+          launchpadConfig,
+          registryEntry,
           idoAuthorityWatermelon,
           idoAccount,
           watermelonMint,
@@ -149,6 +226,27 @@ describe("solana-launchpad", () => {
         },
       }
     );
+
+    await approveIdo(program, idoAccount);
+  });
+
+  it("should rotate the merkle root", async () => {
+    const [[idoAccount]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    await program.rpc.updateMerkleRoot(GOLD_TIER, [...lateRoot], {
+      accounts: {
+        idoAuthority: provider.wallet.publicKey,
+        idoAccount,
+      },
+    });
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(
+      Buffer.from(idoAccountInfo.tiers[GOLD_TIER].merkleRoot).equals(lateRoot)
+    );
   });
 
   let userUsdc: anchor.web3.PublicKey;
@@ -189,24 +287,79 @@ describe("solana-launchpad", () => {
     let userUsdcAccountInfo = await getTokenAccount(provider, userUsdc);
     assert.ok(userUsdcAccountInfo.amount.eq(whitelistDeposit));
 
-    const proof = getProof(tree, program.provider.wallet.publicKey);
+    const [whitelistLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      program.provider.wallet.publicKey,
+      GOLD_TIER,
+      program.programId
+    );
+    const [silverLedger] = await findWhitelistLedgerAddress(
+      idoName,
+      program.provider.wallet.publicKey,
+      SILVER_TIER,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      program.provider.wallet.publicKey,
+      program.programId
+    );
+
+    const proof = getProof(
+      lateTree,
+      program.provider.wallet.publicKey,
+      whitelistMaxAmount
+    );
 
     const amountOut = whitelistDeposit
-      .mul(watermelonIdoPrivateAmount)
-      .div(privateTargetInvestment);
-    await program.rpc.exchangeUsdcForWatermelon(proof, amountOut, {
-      accounts: {
-        userAuthority: program.provider.wallet.publicKey,
-        idoAccount,
-        userUsdc,
-        userWatermelon,
-        usdcMint,
-        watermelonMint,
-        poolUsdc,
-        poolWatermelon,
-        tokenProgram: TOKEN_PROGRAM_ID,
-      },
-    });
+      .mul(goldIdoAmount)
+      .div(goldTargetInvestment);
+    const accounts = {
+      userAuthority: program.provider.wallet.publicKey,
+      idoAccount,
+      userUsdc,
+      userWatermelon,
+      usdcMint,
+      watermelonMint,
+      poolUsdc,
+      poolWatermelon,
+      whitelistLedger,
+      vesting,
+      systemProgram: anchor.web3.SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+    // The gold proof does not verify against the silver root.
+    await assert.rejects(
+      program.rpc.exchangeUsdcForWatermelon(
+        SILVER_TIER,
+        proof,
+        whitelistMaxAmount,
+        amountOut,
+        { accounts: { ...accounts, whitelistLedger: silverLedger } }
+      ),
+      (err: any) => err.msg === "Given proof is invalid"
+    );
+
+    await program.rpc.exchangeUsdcForWatermelon(
+      GOLD_TIER,
+      proof,
+      whitelistMaxAmount,
+      amountOut,
+      { accounts }
+    );
+
+    // A second purchase of the same size would go over the allocation.
+    await assert.rejects(
+      program.rpc.exchangeUsdcForWatermelon(
+        GOLD_TIER,
+        proof,
+        whitelistMaxAmount,
+        amountOut,
+        { accounts }
+      ),
+      (err: any) => err.msg === "Whitelist allocation exceeded"
+    );
     const prevAmount = userUsdcAccountInfo.amount;
     userUsdcAccountInfo = await getTokenAccount(provider, userUsdc);
     assert.ok(userUsdcAccountInfo.amount.eq(prevAmount.sub(whitelistDeposit)));
@@ -216,6 +369,21 @@ describe("solana-launchpad", () => {
     );
     assert.ok(userWatermelonAccountInfo.amount.eq(amountOut));
     userWatermelonAmount = amountOut;
+
+    const ledger = await program.account.whitelistLedger.fetch(
+      whitelistLedger
+    );
+    assert.ok(ledger.userAuthority.equals(program.provider.wallet.publicKey));
+    assert.ok(ledger.idoAccount.equals(idoAccount));
+    assert.ok(ledger.tier === GOLD_TIER);
+    assert.ok(ledger.maxAmount.eq(whitelistMaxAmount));
+    assert.ok(ledger.usdcPaid.eq(whitelistDeposit));
+    assert.ok(ledger.watermelonReceived.eq(amountOut));
+
+    const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
+    assert.ok(idoAccountInfo.tiers[GOLD_TIER].sold.eq(amountOut));
+    assert.ok(idoAccountInfo.tiers[SILVER_TIER].sold.eq(new anchor.BN(0)));
+    assert.ok(idoAccountInfo.privateSold.eq(amountOut));
   });
 
   const firstDeposit = new anchor.BN(10_000_349);
@@ -251,6 +419,12 @@ describe("solana-launchpad", () => {
     let userUsdcAccountInfo = await getTokenAccount(provider, userUsdc);
     assert.ok(userUsdcAccountInfo.amount.eq(firstDeposit));
 
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      program.provider.wallet.publicKey,
+      program.programId
+    );
+
     // send transaction
     await program.rpc.exchangeUsdcForRedeemable(firstDeposit, {
       accounts: {
@@ -261,6 +435,8 @@ describe("solana-launchpad", () => {
         usdcMint,
         redeemableMint,
         poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
       preInstructions: [
@@ -277,6 +453,37 @@ describe("solana-launchpad", () => {
         }),
       ],
     });
+
+    const depositLedgerInfo = await program.account.depositLedger.fetch(
+      depositLedger
+    );
+    assert.ok(depositLedgerInfo.usdcDeposited.eq(firstDeposit));
+
+    // Tickets smaller than the minimum contribution are rejected.
+    const smallDeposit = new anchor.BN(1);
+    await usdcMintAccount.mintTo(
+      userUsdc,
+      provider.wallet.publicKey,
+      [],
+      smallDeposit.toNumber()
+    );
+    await assert.rejects(
+      program.rpc.exchangeUsdcForRedeemable(smallDeposit, {
+        accounts: {
+          userAuthority: program.provider.wallet.publicKey,
+          idoAccount,
+          userUsdc,
+          userRedeemable,
+          usdcMint,
+          redeemableMint,
+          poolUsdc,
+          depositLedger,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      }),
+      (err: any) => err.msg === "Deposit is below the minimum contribution"
+    );
   });
 
   let secondUserKeypair = anchor.web3.Keypair.generate();
@@ -323,6 +530,12 @@ describe("solana-launchpad", () => {
     let userUsdcAccountInfo = await getTokenAccount(provider, secondUserUsdc);
     assert.ok(userUsdcAccountInfo.amount.eq(secondDeposit));
 
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      secondUserKeypair.publicKey,
+      program.programId
+    );
+
     // send transaction
     await program.rpc.exchangeUsdcForRedeemable(secondDeposit, {
       accounts: {
@@ -333,6 +546,8 @@ describe("solana-launchpad", () => {
         usdcMint,
         redeemableMint,
         poolUsdc,
+        depositLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
       preInstructions: [
@@ -353,14 +568,71 @@ describe("solana-launchpad", () => {
     });
   });
 
+  const secondWithdrawal = new anchor.BN(3_000_000);
+  it("should exchange redeemable for USDC (second user)", async () => {
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
+
+    const [secondUserRedeemable] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          secondUserKeypair.publicKey.toBuffer(),
+          Buffer.from(idoName),
+          Buffer.from("user_redeemable"),
+        ],
+        program.programId
+      );
+
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      secondUserKeypair.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeRedeemableForUsdc(secondWithdrawal, {
+      accounts: {
+        userAuthority: secondUserKeypair.publicKey,
+        idoAccount,
+        userUsdc: secondUserUsdc,
+        userRedeemable: secondUserRedeemable,
+        poolUsdc,
+        redeemableMint,
+        usdcMint,
+        depositLedger,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [secondUserKeypair],
+    });
+
+    const userUsdcAccountInfo = await getTokenAccount(
+      provider,
+      secondUserUsdc
+    );
+    assert.ok(userUsdcAccountInfo.amount.eq(secondWithdrawal));
+
+    const userRedeemableInfo = await getTokenAccount(
+      provider,
+      secondUserRedeemable
+    );
+    assert.ok(
+      userRedeemableInfo.amount.eq(secondDeposit.sub(secondWithdrawal))
+    );
+
+    // The withdrawal frees up room under the max contribution.
+    const depositLedgerInfo = await program.account.depositLedger.fetch(
+      depositLedger
+    );
+    assert.ok(
+      depositLedgerInfo.usdcDeposited.eq(secondDeposit.sub(secondWithdrawal))
+    );
+  });
+
   it("should exchange redeemable for watermelon", async () => {
     if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
       await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
     }
-    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
-      idoName,
-      program.programId
-    );
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
 
     const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
       [Buffer.from(idoName), Buffer.from("pool_watermelon")],
@@ -376,6 +648,18 @@ describe("solana-launchpad", () => {
       program.programId
     );
 
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      program.provider.wallet.publicKey,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      program.provider.wallet.publicKey,
+      program.programId
+    );
+
     await program.rpc.exchangeRedeemableForWatermelon(firstDeposit, {
       accounts: {
         userAuthority: program.provider.wallet.publicKey,
@@ -385,6 +669,11 @@ describe("solana-launchpad", () => {
         watermelonMint,
         userRedeemable,
         userWatermelon,
+        userUsdc,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
     });
@@ -408,10 +697,8 @@ describe("solana-launchpad", () => {
     if (Date.now() < idoTimes.endIdo.toNumber() * 1000) {
       await sleep(idoTimes.endIdo.toNumber() * 1000 - Date.now() + 2000);
     }
-    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
-      idoName,
-      program.programId
-    );
+    const [[idoAccount], [redeemableMint], [poolUsdc]] =
+      await findRelatedProgramAddress(idoName, program.programId);
 
     const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
       [Buffer.from(idoName), Buffer.from("pool_watermelon")],
@@ -434,7 +721,20 @@ describe("solana-launchpad", () => {
       provider
     );
 
-    await program.rpc.exchangeRedeemableForWatermelon(secondDeposit, {
+    const secondRedeemable = secondDeposit.sub(secondWithdrawal);
+    const [depositLedger] = await findDepositLedgerAddress(
+      idoName,
+      secondUserKeypair.publicKey,
+      program.programId
+    );
+
+    const [vesting] = await findVestingAddress(
+      idoName,
+      secondUserKeypair.publicKey,
+      program.programId
+    );
+
+    await program.rpc.exchangeRedeemableForWatermelon(secondRedeemable, {
       accounts: {
         userAuthority: secondUserKeypair.publicKey,
         userRedeemable: secondUserRedeemable,
@@ -443,6 +743,11 @@ describe("solana-launchpad", () => {
         poolWatermelon,
         redeemableMint,
         watermelonMint,
+        userUsdc: secondUserUsdc,
+        poolUsdc,
+        usdcMint,
+        depositLedger,
+        vesting,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
       signers: [secondUserKeypair],
@@ -454,13 +759,53 @@ describe("solana-launchpad", () => {
 
     const idoAccountInfo = await program.account.idoAccount.fetch(idoAccount);
 
-    const amountOut = secondDeposit
+    const amountOut = secondRedeemable
       .mul(watermelonIdoPublicAmount)
       .div(idoAccountInfo.poolInfo.redeemableMinted);
 
     assert.ok(secondUserWatermelonInfo.amount.eq(amountOut));
   });
 
+  it("should withdraw unsold private watermelon", async () => {
+    const [[idoAccount], [redeemableMint]] = await findRelatedProgramAddress(
+      idoName,
+      program.programId
+    );
+
+    const [poolWatermelon] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+    const accounts = {
+      payer: provider.wallet.publicKey,
+      userAuthority: provider.wallet.publicKey,
+      userWatermelon: idoAuthorityWatermelon,
+      idoAccount,
+      poolWatermelon,
+      redeemableMint,
+      watermelonMint,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+    await program.rpc.withdrawUnsoldWatermelon({ accounts });
+
+    // Everything but the gold purchase was left unsold.
+    const idoAuthorityWatermelonInfo = await getTokenAccount(
+      provider,
+      idoAuthorityWatermelon
+    );
+    assert.ok(
+      idoAuthorityWatermelonInfo.amount.eq(
+        watermelonIdoPrivateAmount.sub(userWatermelonAmount)
+      )
+    );
+
+    await assert.rejects(
+      program.rpc.withdrawUnsoldWatermelon({ accounts }),
+      (err: any) => err.msg === "No unsold tokens to withdraw"
+    );
+  });
+
   function PoolBumps() {
     this.idoAccount;
     this.redeemableMint;
@@ -468,10 +813,42 @@ describe("solana-launchpad", () => {
     this.poolUsdc;
   }
 
+  function AuctionParams() {
+    this.startPrice = new anchor.BN(0);
+    this.floorPrice = new anchor.BN(0);
+    this.stepDuration = new anchor.BN(0);
+  }
+
+  function LbpParams() {
+    this.startWeight = new anchor.BN(0);
+    this.endWeight = new anchor.BN(0);
+    this.virtualUsdc = new anchor.BN(0);
+  }
+
+  function SealedBidParams() {
+    this.minPrice = new anchor.BN(0);
+    this.tickSize = new anchor.BN(0);
+  }
+
+  function VestingParams() {
+    // Nothing vests, everything unlocks at TGE.
+    this.tgeUnlockBps = new anchor.BN(10_000);
+    this.cliff = new anchor.BN(0);
+    this.period = new anchor.BN(0);
+  }
+
+  function LotteryParams() {
+    this.seedHash = new Array(32).fill(0);
+    this.ticketSize = new anchor.BN(0);
+    this.endRegistration = new anchor.BN(0);
+    this.endDraw = new anchor.BN(0);
+  }
+
   function IdoTimes() {
     this.startIdo;
     this.endWhitelisted;
     this.endDeposits;
+    this.endReveal;
     this.endIdo;
   }
 });
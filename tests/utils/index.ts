@@ -3,6 +3,7 @@ import { MerkleTree } from "merkletreejs";
 import * as anchor from "@project-serum/anchor";
 import * as serumCmn from "@project-serum/common";
 import keccak256 from "keccak256";
+import { faker } from "@faker-js/faker";
 import { Token, ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
 
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
@@ -78,8 +79,47 @@ export async function createWrapNativeAccount(
 export function hash(value: string | number | anchor.BN | Buffer) {
   return keccak256(value);
 }
-export function getProof(tree: MerkleTree, addr: anchor.web3.PublicKey) {
-  const leaf = hash(addr.toBuffer());
+export function whitelistLeaf(
+  addr: anchor.web3.PublicKey,
+  maxAmount: anchor.BN
+) {
+  return hash(
+    Buffer.concat([addr.toBuffer(), maxAmount.toArrayLike(Buffer, "le", 8)])
+  );
+}
+
+export function bidCommitment(price: anchor.BN, amount: anchor.BN, salt: Buffer) {
+  return hash(
+    Buffer.concat([
+      price.toArrayLike(Buffer, "le", 8),
+      amount.toArrayLike(Buffer, "le", 8),
+      salt,
+    ])
+  );
+}
+
+export function allowlistMessage(
+  idoAccount: anchor.web3.PublicKey,
+  user: anchor.web3.PublicKey,
+  tier: number,
+  maxAmount: anchor.BN,
+  expiry: anchor.BN
+) {
+  return Buffer.concat([
+    idoAccount.toBuffer(),
+    user.toBuffer(),
+    Buffer.from([tier]),
+    maxAmount.toArrayLike(Buffer, "le", 8),
+    expiry.toArrayLike(Buffer, "le", 8),
+  ]);
+}
+
+export function getProof(
+  tree: MerkleTree,
+  addr: anchor.web3.PublicKey,
+  maxAmount: anchor.BN
+) {
+  const leaf = whitelistLeaf(addr, maxAmount);
 
   return tree.getProof(leaf).map((p) => p.data);
 }
@@ -108,6 +148,390 @@ export async function findRelatedProgramAddress(
   return [ido, redeemableMint, poolUsdc];
 }
 
+export async function findLaunchpadConfigAddress(
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("launchpad_config")],
+    programId
+  );
+}
+
+// Upgradeable loader account holding the program's upgrade authority.
+export async function findProgramDataAddress(
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+}
+
+// The launchpad config is a singleton shared by every test file, it is created
+// by whichever runs first, with no fee and the provider wallet as treasury.
+// The provider wallet deployed the program, so as its upgrade authority it
+// becomes the launchpad admin.
+export async function initLaunchpad(program: anchor.Program) {
+  const [launchpadConfig, bump] = await findLaunchpadConfigAddress(
+    program.programId
+  );
+  const [programData] = await findProgramDataAddress(program.programId);
+
+  const info = await program.account.launchpadConfig.fetchNullable(
+    launchpadConfig
+  );
+  if (info === null) {
+    await program.rpc.initLaunchpad(
+      bump,
+      program.provider.wallet.publicKey,
+      new anchor.BN(0),
+      [],
+      {
+        accounts: {
+          admin: program.provider.wallet.publicKey,
+          launchpadConfig,
+          programData,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+  }
+  return launchpadConfig;
+}
+
+export async function findRegistryEntryAddress(
+  index: anchor.BN,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("ido_registry"), index.toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+}
+
+// Registry entry the next `initializePool` will create.
+export async function findNextRegistryEntryAddress(program: anchor.Program) {
+  const [launchpadConfig] = await findLaunchpadConfigAddress(
+    program.programId
+  );
+  const { numIdos } = await program.account.launchpadConfig.fetch(
+    launchpadConfig
+  );
+  const [registryEntry] = await findRegistryEntryAddress(
+    numIdos,
+    program.programId
+  );
+  return registryEntry;
+}
+
+// Pools open only once the launchpad admin, the provider wallet in tests, has
+// approved them.
+export async function approveIdo(
+  program: anchor.Program,
+  idoAccount: anchor.web3.PublicKey
+) {
+  const [launchpadConfig] = await findLaunchpadConfigAddress(
+    program.programId
+  );
+  await program.rpc.reviewIdo(true, {
+    accounts: {
+      admin: program.provider.wallet.publicKey,
+      launchpadConfig,
+      idoAccount,
+    },
+  });
+}
+
+// Sale config of a plain pool: floating price, no caps, no vesting and no
+// lottery. `createIdo` callers override only the parts they exercise.
+export function defaultPoolParams() {
+  return {
+    softCap: new anchor.BN(0),
+    hardCap: new anchor.BN(0),
+    minContribution: new anchor.BN(0),
+    maxContribution: new anchor.BN(0),
+    saleMode: { floating: {} },
+    fixedPrice: new anchor.BN(0),
+    auction: {
+      startPrice: new anchor.BN(0),
+      floorPrice: new anchor.BN(0),
+      stepDuration: new anchor.BN(0),
+    },
+    lbp: {
+      startWeight: new anchor.BN(0),
+      endWeight: new anchor.BN(0),
+      virtualUsdc: new anchor.BN(0),
+    },
+    sealedBid: { minPrice: new anchor.BN(0), tickSize: new anchor.BN(0) },
+    privateVesting: {
+      tgeUnlockBps: new anchor.BN(10_000),
+      cliff: new anchor.BN(0),
+      period: new anchor.BN(0),
+    },
+    publicVesting: {
+      tgeUnlockBps: new anchor.BN(10_000),
+      cliff: new anchor.BN(0),
+      period: new anchor.BN(0),
+    },
+    lottery: {
+      seedHash: new Array(32).fill(0),
+      ticketSize: new anchor.BN(0),
+      endRegistration: new anchor.BN(0),
+      endDraw: new anchor.BN(0),
+    },
+  };
+}
+
+export type IdoOverrides = {
+  idoName?: string;
+  // Raise in this mint instead of a freshly created one.
+  usdcMint?: anchor.web3.PublicKey;
+  numIdoTokensPublic?: anchor.BN;
+  params?: Partial<Record<keyof ReturnType<typeof defaultPoolParams>, any>>;
+  // Phase ends, in seconds from now. `endReveal` defaults to `endDeposits`.
+  times?: {
+    startIdo: number;
+    endWhitelisted: number;
+    endDeposits: number;
+    endReveal?: number;
+    endIdo: number;
+  };
+  // Missing `merkleRoot` and `sold` default to zero.
+  tiers?: any[];
+  // Leave the pool pending review.
+  pending?: boolean;
+};
+
+// Creates the mints and the ido authority's watermelon, then initializes and
+// approves a pool funded with its public allocation and every tier.
+export async function createIdo(
+  program: anchor.Program,
+  overrides: IdoOverrides = {}
+) {
+  const provider = program.provider;
+  const launchpadConfig = await initLaunchpad(program);
+
+  const idoName = overrides.idoName ?? faker.name.firstName().slice(0, 10);
+  let usdcMintAccount: Token | undefined;
+  let usdcMint = overrides.usdcMint;
+  if (usdcMint === undefined) {
+    usdcMintAccount = await createMint(provider);
+    usdcMint = usdcMintAccount.publicKey;
+  }
+  const watermelonMintAccount = await createMint(provider);
+  const watermelonMint = watermelonMintAccount.publicKey;
+
+  const numIdoTokensPublic =
+    overrides.numIdoTokensPublic ?? new anchor.BN(5_000_000);
+  const tiers = (overrides.tiers ?? []).map((tier) => ({
+    merkleRoot: new Array(32).fill(0),
+    sold: new anchor.BN(0),
+    ...tier,
+  }));
+  const totalIdoTokens = tiers.reduce(
+    (total, tier) => total.add(tier.numIdoTokens),
+    numIdoTokensPublic
+  );
+
+  const idoAuthorityWatermelon = await createTokenAccount(
+    provider,
+    watermelonMint,
+    provider.wallet.publicKey
+  );
+  await watermelonMintAccount.mintTo(
+    idoAuthorityWatermelon,
+    provider.wallet.publicKey,
+    [],
+    totalIdoTokens.toNumber()
+  );
+
+  const [
+    [idoAccount, idoAccountBump],
+    [redeemableMint, redeemableMintBump],
+    [poolUsdc, poolUsdcBump],
+  ] = await findRelatedProgramAddress(idoName, program.programId);
+
+  const [poolWatermelon, poolWatermelonBump] =
+    await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(idoName), Buffer.from("pool_watermelon")],
+      program.programId
+    );
+
+  const times = overrides.times ?? {
+    startIdo: 5,
+    endWhitelisted: 6,
+    endDeposits: 15,
+    endIdo: 16,
+  };
+  const nowBn = new anchor.BN(Date.now() / 1000);
+  const idoTimes = {
+    startIdo: nowBn.addn(times.startIdo),
+    endWhitelisted: nowBn.addn(times.endWhitelisted),
+    endDeposits: nowBn.addn(times.endDeposits),
+    endReveal: nowBn.addn(times.endReveal ?? times.endDeposits),
+    endIdo: nowBn.addn(times.endIdo),
+  };
+
+  const registryEntry = await findNextRegistryEntryAddress(program);
+
+  await program.rpc.initializePool(
+    idoName,
+    {
+      idoAccount: idoAccountBump,
+      redeemableMint: redeemableMintBump,
+      poolWatermelon: poolWatermelonBump,
+      poolUsdc: poolUsdcBump,
+    },
+    numIdoTokensPublic,
+    { ...defaultPoolParams(), ...overrides.params },
+    idoTimes,
+    tiers,
+    {
+      accounts: {
+        idoAuthority: provider.wallet.publicKey,
+        launchpadConfig,
+        registryEntry,
+        idoAuthorityWatermelon,
+        idoAccount,
+        redeemableMint,
+        watermelonMint,
+        poolWatermelon,
+        usdcMint,
+        poolUsdc,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+    }
+  );
+
+  if (!overrides.pending) {
+    await approveIdo(program, idoAccount);
+  }
+  const { registryIndex } = await program.account.idoAccount.fetch(idoAccount);
+
+  return {
+    idoName,
+    idoAccount,
+    redeemableMint,
+    poolUsdc,
+    poolWatermelon,
+    registryEntry,
+    registryIndex,
+    usdcMint,
+    usdcMintAccount,
+    watermelonMint,
+    watermelonMintAccount,
+    idoAuthorityWatermelon,
+    idoTimes,
+    launchpadConfig,
+  };
+}
+
+export async function findWhitelistLedgerAddress(
+  idoName: string,
+  user: anchor.web3.PublicKey,
+  tier: number,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [
+      user.toBuffer(),
+      Buffer.from(idoName),
+      Buffer.from("whitelist_ledger"),
+      Buffer.from([tier]),
+    ],
+    programId
+  );
+}
+
+export async function findDepositLedgerAddress(
+  idoName: string,
+  user: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [user.toBuffer(), Buffer.from(idoName), Buffer.from("deposit_ledger")],
+    programId
+  );
+}
+
+export async function findVestingAddress(
+  idoName: string,
+  user: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [user.toBuffer(), Buffer.from(idoName), Buffer.from("vesting")],
+    programId
+  );
+}
+
+export async function findBidAddress(
+  idoName: string,
+  user: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [user.toBuffer(), Buffer.from(idoName), Buffer.from("bid")],
+    programId
+  );
+}
+
+export async function findLotteryTicketAddress(
+  idoName: string,
+  user: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [user.toBuffer(), Buffer.from(idoName), Buffer.from("lottery_ticket")],
+    programId
+  );
+}
+
+export async function findStakePoolAddress(
+  programId: anchor.web3.PublicKey
+): Promise<[FindProgramAddress, FindProgramAddress]> {
+  const stakePool = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("stake_pool")],
+    programId
+  );
+  const stakeVault = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("stake_vault")],
+    programId
+  );
+  return [stakePool, stakeVault];
+}
+
+export async function findStakeAccountAddress(
+  user: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [user.toBuffer(), Buffer.from("stake_account")],
+    programId
+  );
+}
+
+// Snapshots are tied to the pool's registry index, so they don't carry over to a
+// pool registered again under the same name.
+export async function findStakeSnapshotAddress(
+  idoName: string,
+  registryIndex: anchor.BN,
+  user: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): Promise<FindProgramAddress> {
+  return anchor.web3.PublicKey.findProgramAddress(
+    [
+      user.toBuffer(),
+      Buffer.from(idoName),
+      registryIndex.toArrayLike(Buffer, "le", 8),
+      Buffer.from("stake_snapshot"),
+    ],
+    programId
+  );
+}
+
 export async function createATA(
   userKeypair: anchor.web3.Keypair | Wallet,
   mint: anchor.web3.PublicKey,